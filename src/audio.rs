@@ -0,0 +1,289 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+
+/// Samples per onset frame.
+const HOP: usize = 512;
+/// Seconds of onset history used to estimate tempo and phase.
+const HISTORY: f32 = 6.0;
+const BPM_MIN: f32 = 70.0;
+const BPM_MAX: f32 = 180.0;
+
+/// A clock update, with the same meaning as the `/vdj/*` OSC messages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Beat {
+    /// Seconds of audio processed.
+    pub t: f32,
+    pub bpm: f32,
+    /// Beats into the current 16 beat phrase.
+    pub phi: f32,
+}
+
+/// Streaming tempo and beat phase estimator.
+///
+/// Onsets are taken from the rectified rise in log energy of each `HOP`
+/// samples. Tempo is the strongest autocorrelation lag of the onset history,
+/// and phase the offset whose comb of beats lines up with the most onset energy.
+pub struct BeatTracker {
+    rate: f32,
+    frame: Vec<f32>,
+    energy: f32,
+    onsets: VecDeque<f32>,
+    frames: u64,
+
+    period: Option<f32>,
+    last_beat: f32,
+    beats: u64,
+}
+
+impl BeatTracker {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: rate as f32,
+            frame: Vec::with_capacity(HOP),
+            energy: 0.0,
+            onsets: VecDeque::new(),
+            frames: 0,
+
+            period: None,
+            last_beat: 0.0,
+            beats: 0,
+        }
+    }
+
+    fn fps(&self) -> f32 {
+        self.rate / HOP as f32
+    }
+
+    /// Feed mono samples, returning a clock update for each full onset frame
+    /// once a tempo has been found.
+    pub fn process(&mut self, samples: &[f32]) -> Vec<Beat> {
+        let mut beats = vec![];
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() == HOP {
+                if let Some(beat) = self.onset_frame() {
+                    beats.push(beat);
+                }
+                self.frame.clear();
+            }
+        }
+        beats
+    }
+
+    fn onset_frame(&mut self) -> Option<Beat> {
+        let energy = (self.frame.iter().map(|s| s * s).sum::<f32>() / HOP as f32 + 1e-9).ln();
+        let onset = (energy - self.energy).max(0.0);
+        self.energy = energy;
+
+        let len = (HISTORY * self.fps()) as usize;
+        self.onsets.push_back(onset);
+        if self.onsets.len() > len {
+            self.onsets.pop_front();
+        }
+        self.frames += 1;
+
+        // Re-estimate twice a second once the history is full.
+        if self.onsets.len() == len && self.frames % (self.fps() as u64 / 2).max(1) == 0 {
+            self.estimate();
+        }
+
+        let period = self.period?;
+        let now = self.frames as f32;
+        while now - self.last_beat >= period {
+            self.last_beat += period;
+            self.beats += 1;
+        }
+
+        let phase = (now - self.last_beat) / period;
+        Some(Beat {
+            t: now / self.fps(),
+            bpm: 60.0 * self.fps() / period,
+            phi: (self.beats % 16) as f32 + phase,
+        })
+    }
+
+    fn estimate(&mut self) {
+        let env = self.onsets.make_contiguous();
+        let mean = env.iter().sum::<f32>() / env.len() as f32;
+        let env: Vec<f32> = env.iter().map(|x| x - mean).collect();
+
+        let fps = self.fps();
+        let lag_min = (60.0 * fps / BPM_MAX).floor() as usize;
+        let lag_max = (60.0 * fps / BPM_MIN).ceil() as usize;
+        let acf = |lag: usize| env.iter().zip(&env[lag..]).map(|(a, b)| a * b).sum::<f32>();
+
+        let scores: Vec<f32> = (lag_min..=lag_max + 1).map(acf).collect();
+        let Some((i, _)) = scores[1..scores.len() - 1]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+        else { return };
+        let i = i + 1;
+        if scores[i] <= 0.0 {
+            return;
+        }
+
+        // Parabolic interpolation around the best lag.
+        let (a, b, c) = (scores[i - 1], scores[i], scores[i + 1]);
+        let shift = match a - 2.0 * b + c {
+            d if d.abs() > f32::EPSILON => 0.5 * (a - c) / d,
+            _ => 0.0,
+        };
+        let period = (lag_min + i) as f32 + shift.clamp(-0.5, 0.5);
+
+        // Phase: the offset from the newest frame whose comb collects the most onset energy.
+        let n = env.len();
+        let offset = (0..period as usize)
+            .max_by(|&a, &b| {
+                let comb = |o: usize| {
+                    (0..)
+                        .map(|k| o + (k as f32 * period) as usize)
+                        .take_while(|&j| j < n)
+                        .map(|j| env[n - 1 - j])
+                        .sum::<f32>()
+                };
+                comb(a).total_cmp(&comb(b))
+            })
+            .unwrap_or(0);
+
+        let last_beat = self.frames as f32 - 1.0 - offset as f32;
+        if self.period.is_none() {
+            self.last_beat = last_beat;
+        } else {
+            // Nudge towards the measured phase rather than jumping, keeping the beat count.
+            let err = (last_beat - self.last_beat).rem_euclid(period);
+            let err = if err > period / 2.0 { err - period } else { err };
+            self.last_beat += 0.25 * err;
+        }
+        self.period = Some(period);
+    }
+}
+
+/// Track every sample of a WAV file as fast as possible.
+pub fn analyze_wav<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Beat>> {
+    let (rate, samples) = read_wav(path)?;
+    let mut tracker = BeatTracker::new(rate);
+    Ok(tracker.process(&samples))
+}
+
+pub fn read_wav<P: AsRef<Path>>(path: P) -> anyhow::Result<(u32, Vec<f32>)> {
+    let mut reader = hound::WavReader::open(path.as_ref())
+        .with_context(|| format!("opening {}", path.as_ref().display()))?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect::<Result<_, _>>()?
+        },
+    };
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    Ok((spec.sample_rate, mono))
+}
+
+/// Where to take audio from for `ClockSource::Audio` or LTC.
+#[derive(Clone, Debug)]
+pub enum AudioInput {
+    /// ALSA capture device, e.g. `default` or `hw:1,0`.
+    Alsa(String),
+    /// WAV file, played back in real time.
+    Wav(String),
+}
+
+impl AudioInput {
+    pub fn parse(s: &str) -> Self {
+        match s.ends_with(".wav") {
+            true => AudioInput::Wav(s.into()),
+            false => AudioInput::Alsa(s.into()),
+        }
+    }
+}
+
+/// Start tracking `input` on a background thread.
+pub fn spawn(input: AudioInput) -> mpsc::Receiver<Beat> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut tracker = None;
+        let res = stream(&input, |rate, samples| {
+            let tracker = tracker.get_or_insert_with(|| BeatTracker::new(rate));
+            if let Some(beat) = tracker.process(samples).last() {
+                tx.send(*beat)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = res {
+            log::warn!("Audio clock {:?} stopped: {:?}", input, e);
+        }
+    });
+    rx
+}
+
+/// Read `input` in real time, calling `f` with the sample rate and each chunk of mono samples.
+pub fn stream<F>(input: &AudioInput, f: F) -> anyhow::Result<()>
+where
+    F: FnMut(u32, &[f32]) -> anyhow::Result<()>,
+{
+    match input {
+        AudioInput::Alsa(device) => run_alsa(device, f),
+        AudioInput::Wav(path) => run_wav(path, f),
+    }
+}
+
+fn run_alsa<F: FnMut(u32, &[f32]) -> anyhow::Result<()>>(device: &str, mut f: F) -> anyhow::Result<()> {
+    use alsa::{Direction, ValueOr};
+    use alsa::pcm::{PCM, HwParams, Format, Access};
+
+    let pcm = PCM::new(device, Direction::Capture, false)?;
+    {
+        let hwp = HwParams::any(&pcm)?;
+        hwp.set_channels(1)?;
+        hwp.set_rate(44100, ValueOr::Nearest)?;
+        hwp.set_format(Format::s16())?;
+        hwp.set_access(Access::RWInterleaved)?;
+        pcm.hw_params(&hwp)?;
+    }
+    let rate = pcm.hw_params_current()?.get_rate()?;
+    let io = pcm.io_i16()?;
+    pcm.start()?;
+    log::info!("Capturing audio from {} at {}Hz", device, rate);
+
+    let mut buf = [0i16; HOP];
+    loop {
+        let n = match io.readi(&mut buf) {
+            Ok(n) => n,
+            Err(e) => {
+                log::warn!("Audio capture overrun: {:?}", e);
+                pcm.prepare()?;
+                continue;
+            }
+        };
+        let samples: Vec<f32> = buf[..n].iter().map(|&s| s as f32 / 32768.0).collect();
+        f(rate, &samples)?;
+    }
+}
+
+fn run_wav<F: FnMut(u32, &[f32]) -> anyhow::Result<()>>(path: &str, mut f: F) -> anyhow::Result<()> {
+    let (rate, samples) = read_wav(path)?;
+    log::info!("Playing audio from {} at {}Hz", path, rate);
+
+    let start = Instant::now();
+    for (i, chunk) in samples.chunks(HOP).enumerate() {
+        let due = Duration::from_secs_f32((i * HOP) as f32 / rate as f32);
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+        f(rate, chunk)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Frame timing. The main loop, offline rendering and tests all advance the
+//! show through a `Clock`, so they see the same timing for the same frames.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as _};
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait Clock: Send {
+    /// Seconds since the clock started, fixed for the current frame.
+    fn now(&self) -> f32;
+    /// Move to the next frame, waiting for it if the clock runs in real time.
+    /// Returns `false` once the clock has run out.
+    async fn tick(&mut self) -> bool;
+}
+
+/// Real time, ticking every `period`.
+pub struct WallClock {
+    start: Instant,
+    interval: tokio::time::Interval,
+    now: f32,
+}
+
+impl WallClock {
+    pub fn new(period: Duration) -> Self {
+        let mut interval = tokio::time::interval(period);
+        // After a stall, carry on from now rather than rendering the missed frames in a burst.
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        Self {
+            start: Instant::now(),
+            interval,
+            now: 0.0,
+        }
+    }
+}
+
+#[async_trait]
+impl Clock for WallClock {
+    fn now(&self) -> f32 {
+        self.now
+    }
+
+    async fn tick(&mut self) -> bool {
+        self.interval.tick().await;
+        self.now = self.start.elapsed().as_secs_f32();
+        true
+    }
+}
+
+/// Simulated time in fixed steps, as fast as frames can be computed.
+pub struct StepClock {
+    pub dt: f32,
+    /// Stop after this many seconds, or run forever.
+    pub end: Option<f32>,
+    frame: Option<u64>,
+}
+
+impl StepClock {
+    pub fn new(dt: f32, end: Option<f32>) -> Self {
+        Self { dt, end, frame: None }
+    }
+}
+
+#[async_trait]
+impl Clock for StepClock {
+    fn now(&self) -> f32 {
+        // Multiplied rather than summed so long runs don't drift.
+        self.frame.unwrap_or(0) as f32 * self.dt
+    }
+
+    async fn tick(&mut self) -> bool {
+        let frame = self.frame.map_or(0, |f| f + 1);
+        self.frame = Some(frame);
+        self.end.map_or(true, |end| self.now() < end)
+    }
+}
+
+/// Frame times replayed from a recording, as fast as frames can be computed.
+pub struct ReplayClock {
+    times: Vec<f32>,
+    frame: Option<usize>,
+}
+
+impl ReplayClock {
+    pub fn new(times: Vec<f32>) -> Self {
+        Self { times, frame: None }
+    }
+
+    /// Times from the first column of each line, e.g. a `render --dmx` output.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let times = text
+            .lines()
+            .filter_map(|l| l.split_whitespace().next())
+            .map(|t| t.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("parsing {}", path.display()))?;
+        if times.windows(2).any(|w| w[1] < w[0]) {
+            bail!("frame times in {} go backwards", path.display());
+        }
+        Ok(Self::new(times))
+    }
+}
+
+#[async_trait]
+impl Clock for ReplayClock {
+    fn now(&self) -> f32 {
+        self.frame.and_then(|f| self.times.get(f)).copied().unwrap_or(0.0)
+    }
+
+    async fn tick(&mut self) -> bool {
+        let frame = self.frame.map_or(0, |f| f + 1);
+        self.frame = Some(frame);
+        frame < self.times.len()
+    }
+}
@@ -1,6 +1,8 @@
+use serde::{Serialize, Deserialize};
+
 use stagebridge::num::Float;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Color {
     pub a: f32,
     pub r: f32,
@@ -50,6 +52,32 @@ impl Color {
     pub fn a_mul(self, a: f32) -> Self {
         Self { a: self.a * a, r: self.r, g: self.g, b: self.b, w: self.w }
     }
+
+    pub fn mix(self, other: Self, fr: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * fr;
+        Self::argbw(mix(self.a, other.a), mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.w, other.w))
+    }
+
+    /// Look up one of the named constants, case insensitively.
+    pub fn named(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "off"     => Self::OFF,
+            "white"   => Self::WHITE,
+            "rgb"     => Self::RGB,
+            "red"     => Self::RED,
+            "orange"  => Self::ORANGE,
+            "yellow"  => Self::YELLOW,
+            "pea"     => Self::PEA,
+            "lime"    => Self::LIME,
+            "mint"    => Self::MINT,
+            "cyan"    => Self::CYAN,
+            "blue"    => Self::BLUE,
+            "violet"  => Self::VIOLET,
+            "magenta" => Self::MAGENTA,
+            "pink"    => Self::PINK,
+            _ => return None,
+        })
+    }
 }
 
 use stagebridge::midi::device::launchpad_x::types::PaletteColor;
@@ -74,6 +102,37 @@ impl From<PaletteColor> for Color {
     }
 }
 
+impl Color {
+    /// The nearest Launchpad palette color by hue, or `Off` if dark.
+    pub fn palette(self) -> PaletteColor {
+        const PALETTE: [PaletteColor; 11] = [
+            PaletteColor::Red,
+            PaletteColor::Orange,
+            PaletteColor::Yellow,
+            PaletteColor::Pea,
+            PaletteColor::Lime,
+            PaletteColor::Mint,
+            PaletteColor::Cyan,
+            PaletteColor::Blue,
+            PaletteColor::Violet,
+            PaletteColor::Magenta,
+            PaletteColor::Pink,
+        ];
+        let Color { r, g, b, w, a } = self;
+        if a < 0.05 || r.max(g).max(b).max(w) < 0.05 {
+            return PaletteColor::Off;
+        }
+        if w > r.max(g).max(b) || (r > 0.9 && g > 0.9 && b > 0.9) {
+            return PaletteColor::White;
+        }
+        let dist = |p: PaletteColor| {
+            let c = Color::from(p);
+            (c.r - r).powi(2) + (c.g - g).powi(2) + (c.b - b).powi(2)
+        };
+        PALETTE.into_iter().min_by(|&p, &q| dist(p).total_cmp(&dist(q))).unwrap()
+    }
+}
+
 pub use stagebridge::midi::device::launchpad_x::types::Color as PadColor;
 impl From<Color> for PadColor {
     fn from(color: Color) -> Self {
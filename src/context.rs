@@ -1,5 +1,9 @@
 use std::thread;
 use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
 use stagebridge::osc::{self, Osc};
 use tokio::task;
 
@@ -12,9 +16,58 @@ use stagebridge::midi::device::launch_control_xl::{LaunchControlXL, self};
 // use super::beatgrid::BeatGrid;
 // use super::state::StateHolder;
 
+const PAD_PORT: &str = "Launchpad X:Launchpad X LPX MIDI";
+const CTRL_PORT: &str = "Launch Control XL:Launch Control XL";
+
+/// How often to look for devices that were unplugged or plugged back in.
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A MIDI device that may come and go, with subscribers that outlive it.
+struct Device<M, I> {
+    midi: Mutex<Option<Arc<M>>>,
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<I>>>>,
+}
+
+impl<M, I: Clone + Send + 'static> Device<M, I> {
+    fn new() -> Self {
+        Self {
+            midi: Mutex::new(None),
+            subscribers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    fn get(&self) -> Option<Arc<M>> {
+        self.midi.lock().clone()
+    }
+
+    fn subscribe(&self) -> mpsc::Receiver<I> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    /// Start using a newly opened device, forwarding its input to every subscriber.
+    fn attach(&self, midi: M, input: mpsc::Receiver<I>) {
+        let subscribers = Arc::clone(&self.subscribers);
+        thread::spawn(move || {
+            // Ends when the device is dropped.
+            for event in input {
+                subscribers.lock().retain(|tx| tx.send(event.clone()).is_ok());
+            }
+        });
+        *self.midi.lock() = Some(Arc::new(midi));
+    }
+
+    fn detach(&self) {
+        *self.midi.lock() = None;
+    }
+}
+
 pub struct Context {
-    pad: Option<Midi<LaunchpadX>>,
-    ctrl: Option<Midi<LaunchControlXL>>,
+    pad: Device<Midi<LaunchpadX>, launchpad_x::Input>,
+    ctrl: Device<Midi<LaunchControlXL>, launch_control_xl::Input>,
+    /// Bumped whenever a device is reopened, so feedback can be resent.
+    generation: AtomicU64,
 
     // pub beats: BeatGrid,
 
@@ -23,87 +76,118 @@ pub struct Context {
 
 impl Context {
     pub async fn new() -> &'static Self {
-        let pad = match Midi::<LaunchpadX>::open("Launchpad X:Launchpad X LPX MIDI") {
-            Ok(pad) => {
-                use stagebridge::midi::device::launchpad_x::{*, types::*};
-                pad.send(Output::Mode(Mode::Programmer)).await;
-                pad.send(Output::Pressure(Pressure::Off, PressureCurve::Medium)).await;
-                pad.send(Output::Clear).await;
-                Some(pad)
-            },
-            Err(e) => {
-                log::warn!("Failed to open Launchpad: {:?}", e);
-                None
-            }
-        };
+        let ctx: &'static Self = Box::leak(Box::new(Self {
+            pad: Device::new(),
+            ctrl: Device::new(),
+            generation: AtomicU64::new(0),
 
-        let ctrl = match Midi::<LaunchControlXL>::open("Launch Control XL:Launch Control XL") {
-            Ok(ctrl) => Some(ctrl),
-            Err(e) => {
-                log::warn!("Failed to open LaunchControl: {:?}", e);
-                None
-            }
-        };
+            // beats,
+
+            // state,
+        }));
 
         // let beats = BeatGrid::new(128);
         // beats.start(&osc);
 
         // let state = StateHolder::spawn();
 
-        Box::leak(Box::new(Self {
-            pad,
-            ctrl,
+        ctx.scan(true).await;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SCAN_INTERVAL);
+            loop {
+                interval.tick().await;
+                ctx.scan(false).await;
+            }
+        });
 
-            // beats,
+        ctx
+    }
 
-            // state,
-        }))
+    /// Drop devices whose ports have gone away and open any that are missing.
+    async fn scan(&self, first: bool) {
+        let ports = match midir::MidiInput::new("milstrikelive-scan") {
+            Ok(midi) => midi.ports().iter().filter_map(|p| midi.port_name(p).ok()).collect::<Vec<_>>(),
+            Err(e) => {
+                log::warn!("Failed to list MIDI ports: {:?}", e);
+                return;
+            }
+        };
+        let present = |name: &str| ports.iter().any(|p| p.contains(name.split(':').next().unwrap_or(name)));
+
+        if self.pad.get().is_some() && !present(PAD_PORT) {
+            log::warn!("Launchpad disconnected");
+            self.pad.detach();
+        }
+        if self.pad.get().is_none() && (first || present(PAD_PORT)) {
+            match Midi::<LaunchpadX>::open(PAD_PORT) {
+                Ok(pad) => {
+                    use stagebridge::midi::device::launchpad_x::{*, types::*};
+                    pad.send(Output::Mode(Mode::Programmer)).await;
+                    pad.send(Output::Pressure(Pressure::Off, PressureCurve::Medium)).await;
+                    pad.send(Output::Clear).await;
+                    if !first {
+                        log::info!("Launchpad reconnected");
+                    }
+                    let input = pad.subscribe_sync();
+                    self.pad.attach(pad, input);
+                    self.generation.fetch_add(1, Ordering::Relaxed);
+                },
+                Err(e) if first => log::warn!("Failed to open Launchpad: {:?}", e),
+                Err(e) => log::debug!("Failed to reopen Launchpad: {:?}", e),
+            }
+        }
+
+        if self.ctrl.get().is_some() && !present(CTRL_PORT) {
+            log::warn!("LaunchControl disconnected");
+            self.ctrl.detach();
+        }
+        if self.ctrl.get().is_none() && (first || present(CTRL_PORT)) {
+            match Midi::<LaunchControlXL>::open(CTRL_PORT) {
+                Ok(ctrl) => {
+                    if !first {
+                        log::info!("LaunchControl reconnected");
+                    }
+                    let input = ctrl.subscribe_sync();
+                    self.ctrl.attach(ctrl, input);
+                    self.generation.fetch_add(1, Ordering::Relaxed);
+                },
+                Err(e) if first => log::warn!("Failed to open LaunchControl: {:?}", e),
+                Err(e) => log::debug!("Failed to reopen LaunchControl: {:?}", e),
+            }
+        }
     }
 
-    pub fn pad(&self) -> Option<&Midi<LaunchpadX>> {
-        self.pad.as_ref()
+    /// Changes whenever a controller is reopened.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
     }
 
-    pub fn ctrl(&self) -> Option<&Midi<LaunchControlXL>> {
-        self.ctrl.as_ref()
+    pub fn pad(&self) -> Option<Arc<Midi<LaunchpadX>>> {
+        self.pad.get()
     }
 
+    pub fn ctrl(&self) -> Option<Arc<Midi<LaunchControlXL>>> {
+        self.ctrl.get()
+    }
+
+    /// Input from the Launchpad, across reconnects.
     pub fn subscribe_pad(&self) -> mpsc::Receiver<launchpad_x::Input> {
-        match self.pad.as_ref() {
-            Some(pad) => pad.subscribe_sync(),
-            _ => {
-                let (tx, rx) = mpsc::channel();
-                thread::spawn(move || {
-                    let _tx = tx;
-                    thread::park();
-                });
-                rx
-            }
-        }
+        self.pad.subscribe()
     }
 
     pub async fn send_pad(&self, output: launchpad_x::Output) {
-        if let Some(pad) = self.pad.as_ref() {
+        if let Some(pad) = self.pad() {
             pad.send(output).await;
         }
     }
 
+    /// Input from the Launch Control, across reconnects.
     pub fn subscribe_ctrl(&self) -> mpsc::Receiver<launch_control_xl::Input> {
-        match self.ctrl.as_ref() {
-            Some(ctrl) => ctrl.subscribe_sync(),
-            _ => {
-                let (tx, rx) = mpsc::channel();
-                thread::spawn(move || {
-                    let _tx = tx;
-                    thread::park();
-                });
-                rx
-            }
-        }
+        self.ctrl.subscribe()
     }
 
     pub async fn send_ctrl(&self, output: launch_control_xl::Output) {
-        if let Some(ctrl) = self.ctrl.as_ref() {
+        if let Some(ctrl) = self.ctrl() {
             ctrl.send(output).await;
         }
     }
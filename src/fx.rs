@@ -1,64 +1,181 @@
-use std::time::Instant;
-use std::sync::{Arc, Mutex};
+use std::ops;
 
-use stagebridge::num::{Float, Ease, Range};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Serialize, Deserialize};
 
-use crate::{State, Color, Lights, Pd};
+use stagebridge::num::{Float, Ease, Range};
 
-macro_rules! op {
-    ($trait:ident, $boxed:ident, ($($arg:ident : $ty:ty),*) -> $ret:ty) => {
-        pub trait $trait: Send + Sync + 'static {
-            fn box_clone(&self) -> Box<dyn $trait>;
-            fn apply(&mut self, $($arg: $ty),*) -> $ret;
-        }
+use crate::{State, Color, Pd};
 
-        impl<F: FnMut($($ty),*) -> $ret + Send + Sync + Clone + 'static > $trait for F {
-            fn box_clone(&self) -> Box<dyn $trait> {
-                Box::new(self.clone())
-            }
-            fn apply(&mut self, $($arg: $ty),*) -> $ret {
-                (self)($($arg),*)
-            }
-        }
-        pub struct $boxed(Arc<Mutex<Box<dyn $trait>>>);
-        impl $boxed {
-            pub fn new<F: $trait>(func: F) -> Self {
-                Self(Arc::new(Mutex::new(Box::new(func))))
-            }
-            pub fn value(value: $ret) -> Self {
-                Self::new(move |$(_: $ty),*| value)
-            }
-            pub fn apply(&self, $($arg : $ty),*) -> $ret {
-                self.0.lock().unwrap().apply($($arg),*)
-            }
-        }
-        impl<F: FnMut($($ty),*) -> $ret + Send + Sync + Clone + 'static> From<F> for $boxed {
-            fn from(func: F) -> Self {
-                Self::new(func)
-            }
-        }
+/// A color generator.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorOp {
+    Value(Color),
+    Rainbow { pd: Pd },
+}
+impl ColorOp {
+    pub fn value(color: Color) -> Self {
+        ColorOp::Value(color)
+    }
 
-        impl Clone for $boxed {
-            fn clone(&self) -> Self {
-                Self(Arc::clone(&self.0))
-            }
+    pub fn apply(&self, state: &State) -> Color {
+        match self {
+            ColorOp::Value(color) => *color,
+            ColorOp::Rainbow { pd } => Color::hsv(state.phi(*pd), 1.0, 1.0),
         }
-    };
-    ($trait:ident, $boxed:ident, ($($arg:ident : $ty:ty),*)) => {
-        op!($trait, $boxed, ($($arg : $ty),*) -> ());
-    };
+    }
 }
-
-op!(ColorFn, ColorOp, (state: &State) -> Color);
 impl From<Color> for ColorOp {
     fn from(color: Color) -> Self {
         ColorOp::value(color)
     }
 }
-op!(ColorMapFn, ColorMapOp, (state: &State, color: Color) -> Color);
+
+/// A transformation of a generated color, usually of its alpha.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorMapOp {
+    Id,
+    Value(Color),
+    Alpha(f32),
+    Sin { pd: Pd, a: f32, range: f32 },
+    Pulse { pd: Pd, range: ops::Range<f32> },
+    PulseShort { pd: Pd, range: ops::Range<f32> },
+    Ramp { pd: Pd },
+    SawUp { pd: Pd, range: ops::Range<f32> },
+    SawDown { pd: Pd, range: ops::Range<f32> },
+    Tri { pd: Pd, range: ops::Range<f32> },
+    Strobe { pd: Pd, duty: f32, range: ops::Range<f32> },
+    SampleHold { pd: Pd, seed: u64, range: ops::Range<f32> },
+    Noise { pd: Pd, seed: u64, range: ops::Range<f32> },
+    Adsr { pd: Pd, env: Adsr, range: ops::Range<f32> },
+    Once { pd: Pd, op: Box<ColorMapOp> },
+    Compose(Box<ColorMapOp>, Box<ColorMapOp>),
+}
+
+/// When a one-shot effect was started.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trigger {
+    pub phi: f32,
+    pub t0: f32,
+}
+impl Trigger {
+    pub fn at(state: &State) -> Self {
+        Self { phi: state.phi, t0: state.t0 }
+    }
+}
+
+/// The one-shot state of one evaluation site, e.g. `State::map0`. Ops are
+/// plain values, so clones installed at different sites start on their own.
+#[derive(Clone, Debug, Default)]
+pub struct OnceState {
+    op: Option<ColorMapOp>,
+    pub trigger: Option<Trigger>,
+}
+impl OnceState {
+    /// Start `op`'s one-shots if it's newly installed here or was retriggered.
+    pub fn arm(&mut self, op: &ColorMapOp, at: Trigger) {
+        if self.trigger.is_none() || self.op.as_ref() != Some(op) {
+            self.op = Some(op.clone());
+            self.trigger = Some(at);
+        }
+    }
+
+    /// Start over when next armed.
+    pub fn retrigger(&mut self) {
+        self.trigger = None;
+    }
+}
+
 impl ColorMapOp {
+    pub fn value(color: Color) -> Self {
+        ColorMapOp::Value(color)
+    }
+
     pub fn compose(self, other: ColorMapOp) -> ColorMapOp {
-        ColorMapOp::new(move |state: &State, color: Color| other.apply(state, self.apply(state, color)))
+        ColorMapOp::Compose(Box::new(self), Box::new(other))
+    }
+
+    /// Apply with any one-shots held at their start.
+    pub fn apply(&self, state: &State, color: Color) -> Color {
+        self.apply_from(state, None, color)
+    }
+
+    /// Apply with one-shots running from `trigger`.
+    pub fn apply_from(&self, state: &State, trigger: Option<Trigger>, color: Color) -> Color {
+        match self {
+            ColorMapOp::Id => color,
+            ColorMapOp::Value(color) => *color,
+            ColorMapOp::Alpha(fr) => color.a(*fr),
+            ColorMapOp::Sin { pd, a, range } => {
+                let t = state.phi(*pd);
+                color.a(a + t.ssin(1.0) * range)
+            },
+            ColorMapOp::Pulse { pd, range } => {
+                let fr = state.phi(*pd).ease_quad_out();
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::PulseShort { pd, range } => {
+                let fr = state.phi(*pd).ease_cubic_out();
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::Ramp { pd } => {
+                let fr = 1.0 - state.phi(*pd);
+                color.a(fr)
+            },
+            ColorMapOp::SawUp { pd, range } => {
+                let fr = state.phi(*pd);
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::SawDown { pd, range } => {
+                let fr = 1.0 - state.phi(*pd);
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::Tri { pd, range } => {
+                let fr = state.phi(*pd).tri(1.0);
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::Strobe { pd, duty, range } => {
+                let fr = state.phi(*pd).square(1.0, *duty);
+                color.a(fr.lerp(range.clone()))
+            },
+            ColorMapOp::SampleHold { pd, seed, range } => {
+                let (n, _) = state.cycle(*pd);
+                color.a(random(*seed, n).lerp(range.clone()))
+            },
+            ColorMapOp::Noise { pd, seed, range } => {
+                let (n, t) = state.cycle(*pd);
+                let (a, b) = (random(*seed, n), random(*seed, n.wrapping_add(1)));
+                let t = t * t * (3.0 - 2.0 * t);
+                color.a((a + (b - a) * t).lerp(range.clone()))
+            },
+            ColorMapOp::Adsr { pd, env, range } => {
+                let beats = state.phi(*pd) * pd.fr() * state.phi_mul;
+                color.a(env.at(beats).lerp(range.clone()))
+            },
+            ColorMapOp::Once { pd, op } => {
+                let end = pd.fr() - f32::EPSILON;
+                let t = match trigger {
+                    Some(Trigger { phi, t0 }) => {
+                        // phi wraps every 16 beats, so use wall time to tell
+                        // a finished one-shot from one that just started.
+                        let elapsed = (state.t0 - t0) * state.bpm / 60.0;
+                        let wrap = (state.phi - phi + 1.0).rem_euclid(16.0) - 1.0;
+                        if elapsed > end + 1.0 || wrap > end {
+                            end
+                        } else {
+                            wrap.max(0.0)
+                        }
+                    }
+                    None => 0.0,
+                };
+
+                let mut state = state.clone();
+                state.phi = t;
+
+                op.apply_from(&state, trigger, color)
+            },
+            ColorMapOp::Compose(a, b) => b.apply_from(state, trigger, a.apply_from(state, trigger, color)),
+        }
     }
 }
 impl From<Color> for ColorMapOp {
@@ -67,90 +184,103 @@ impl From<Color> for ColorMapOp {
     }
 }
 
-op!(LightFn, LightOp, (state: &State, lights: &mut Lights));
-
-op!(LightColorFn, LightColorOp, (color: Color, lights: &mut Lights));
-
 pub fn rainbow(pd: Pd) -> ColorOp {
-    ColorOp::new(move |state: &State| Color::hsv(state.phi(pd), 1.0, 1.0))
+    ColorOp::Rainbow { pd }
 }
 
 pub fn sin(pd: Pd, a: f32, range: f32) -> ColorMapOp {
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let t = state.phi(pd);
-        color.a(a + t.ssin(1.0) * range)
-    })
+    ColorMapOp::Sin { pd, a, range }
 }
 
-pub fn pulse<R: Into<Range>>(pd: Pd, range: R) -> ColorMapOp {
-    let range = range.into();
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let fr = state.phi(pd).ease_quad_out();
-        color.a(fr.lerp(range))
-    })
+pub fn pulse(pd: Pd, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::Pulse { pd, range }
 }
 
-pub fn pulse_short<R: Into<Range>>(pd: Pd, range: R) -> ColorMapOp {
-    let range = range.into();
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let fr = state.phi(pd).ease_cubic_out();
-        color.a(fr.lerp(range))
-    })
+pub fn pulse_short(pd: Pd, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::PulseShort { pd, range }
 }
 
 pub fn ramp(pd: Pd) -> ColorMapOp {
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let fr = 1.0 - state.phi(pd);
-        color.a(fr)
-    })
+    ColorMapOp::Ramp { pd }
 }
 
-pub fn tri<R: Into<Range>>(pd: Pd, range: R) -> ColorMapOp {
-    let range = range.into();
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let fr = state.phi(pd).tri(1.0);
-        color.a(fr.lerp(range))
-    })
+pub fn saw_up(pd: Pd, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::SawUp { pd, range }
 }
 
-pub fn strobe<R: Into<Range>>(pd: Pd, duty: f32, range: R) -> ColorMapOp {
-    let range = range.into();
-    ColorMapOp::new(move |state: &State, color: Color| {
-        let fr = state.phi(pd).square(1.0, duty);
-        color.a(fr.lerp(range))
-    })
+pub fn saw_down(pd: Pd, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::SawDown { pd, range }
 }
 
-pub fn once(pd: Pd, op: ColorMapOp) -> ColorMapOp {
-    let mut start: Option<f32> = None;
-    let mut done = false;
-    ColorMapOp::new(move |state: &State, color| {
-        let t = match start {
-            Some(phi) => {
-                let wrap = if state.phi < phi {
-                    state.phi + 16.0 - phi
-                } else {
-                    state.phi - phi
-                };
-                if done || wrap > pd.fr() - f32::EPSILON {
-                    done = true;
-                    pd.fr() - f32::EPSILON
-                } else {
-                    wrap
-                }
+pub fn tri(pd: Pd, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::Tri { pd, range }
+}
+
+pub fn strobe(pd: Pd, duty: f32, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::Strobe { pd, duty, range }
+}
+
+/// Deterministic random value in 0..1 for step `n` of the sequence given by `seed`.
+pub fn random(seed: u64, n: u64) -> f32 {
+    StdRng::seed_from_u64(seed ^ n.wrapping_mul(0x9e3779b97f4a7c15)).gen()
+}
+
+/// New random value every `pd`, held until the next.
+pub fn sample_hold(pd: Pd, seed: u64, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::SampleHold { pd, seed, range }
+}
+
+/// Smoothed value noise, easing to a new random value every `pd`.
+pub fn noise(pd: Pd, seed: u64, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::Noise { pd, seed, range }
+}
+
+/// Envelope times in beats. The gate is held open for `hold` beats after each trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Adsr {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub hold: f32,
+}
+
+impl Adsr {
+    pub fn at(&self, beats: f32) -> f32 {
+        let Adsr { attack, decay, sustain, release, hold } = *self;
+        let gate = |t: f32| {
+            if t < attack {
+                t / attack
+            } else if t < attack + decay {
+                1.0 - (1.0 - sustain) * (t - attack) / decay
+            } else {
+                sustain
             }
-            None => {
-                start = Some(state.phi);
-                0.0
-            },
         };
+        if beats < hold {
+            gate(beats)
+        } else if beats < hold + release {
+            gate(hold) * (1.0 - (beats - hold) / release)
+        } else {
+            0.0
+        }
+    }
+}
 
-        let mut state = state.clone();
-        state.phi = t;
+/// Envelope retriggered every `pd`.
+pub fn adsr(pd: Pd, env: Adsr, range: ops::Range<f32>) -> ColorMapOp {
+    ColorMapOp::Adsr { pd, env, range }
+}
+
+/// Envelope triggered once when the op is first applied, e.g. on pad press.
+pub fn adsr_once(env: Adsr, range: ops::Range<f32>) -> ColorMapOp {
+    let len = env.hold + env.release;
+    let pd = Pd((len * 16.0).ceil().max(1.0) as usize, 16);
+    once(pd, adsr(pd, env, range))
+}
 
-        let color = op.apply(&state, color);
-        color
-    })
+pub fn once(pd: Pd, op: ColorMapOp) -> ColorMapOp {
+    ColorMapOp::Once { pd, op: Box::new(op) }
 }
 
 pub fn off() -> ColorMapOp {
@@ -158,10 +288,10 @@ pub fn off() -> ColorMapOp {
 }
 
 pub fn id() -> ColorMapOp {
-    ColorMapOp::new(|_: &State, color| color)
+    ColorMapOp::Id
 }
 
 
 pub fn alpha(fr: f32) -> ColorMapOp {
-    ColorMapOp::new(move |_: &State, color: Color| color.a(fr))
+    ColorMapOp::Alpha(fr)
 }
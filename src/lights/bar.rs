@@ -3,6 +3,8 @@ use stagebridge::num::Float;
 
 use crate::color::Color;
 
+use super::{profile, Attributes};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Bar {
     // pub mode: BarMode,
@@ -21,22 +23,16 @@ impl std::default::Default for Bar {
 }
 
 impl DMXDevice for Bar {
-    fn size(&self) -> usize { 7 }
+    fn size(&self) -> usize { profile("bar").size }
 
     fn encode(&self, buffer: &mut [u8]) {
-        if self.color.w == 0.0 {
-            buffer[0] = self.color.r.byte();
-            buffer[1] = self.color.g.byte();
-            buffer[2] = self.color.b.byte();
-        } else {
-            buffer[0] = self.color.w.byte();
-            buffer[1] = self.color.w.byte();
-            buffer[2] = self.color.w.byte();
-        }
-        // buffer[3]: preset colors
-        // buffer[4]: strobe
-        // buffer[5]: mode
-        buffer[6] = self.color.a.byte();
+        profile("bar").encode(self, buffer);
+    }
+}
+
+impl Attributes for Bar {
+    fn color(&self, _head: u8) -> Option<Color> {
+        Some(self.color)
     }
 }
 
@@ -3,6 +3,8 @@ use stagebridge::num::Float;
 
 use crate::color::Color;
 
+use super::{profile, Attr, Attributes, Value};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Beam {
     pub mode: BeamMode,
@@ -33,30 +35,32 @@ impl std::default::Default for Beam {
 }
 
 impl DMXDevice for Beam {
-    fn size(&self) -> usize { 15 }
+    fn size(&self) -> usize { profile("beam").size }
 
     fn encode(&self, buffer: &mut [u8]) {
-        buffer[0] = self.yaw.byte();
-        // buffer[0] = (self.yaw * (2.0 / 3.0)).byte();
-        buffer[0] = self.yaw.lerp((1.0/3.0)..1.0).byte();
-        // buffer[1]: yaw fine
-        buffer[2] = self.pitch.byte();
-        // buffer[3]: pitch fine
-        buffer[4] = (1.0 - self.speed).byte();
-        buffer[5] = self.color.a.byte();
-        // buffer[6]: strobe
-        buffer[7] = self.color.r.byte();
-        buffer[8] = self.color.g.byte();
-        buffer[9] = self.color.b.byte();
-        buffer[10] = self.color.w.byte();
-        // buffer[11]: color preset
-        buffer[12] = self.mode.byte();
-        // buffer[13]: auto pitch/yaw, reset
-        buffer[14] = self.ring.byte();
+        profile("beam").encode(self, buffer);
     }
 }
 
+impl Attributes for Beam {
+    fn color(&self, _head: u8) -> Option<Color> {
+        Some(self.color)
+    }
 
+    fn value(&self, attr: Attr, _head: u8) -> Option<Value> {
+        match attr {
+            Attr::Pan => Some(Value::Fr(self.yaw)),
+            Attr::Tilt => Some(Value::Fr(self.pitch)),
+            Attr::Speed => Some(Value::Fr(self.speed)),
+            Attr::Mode => Some(self.mode.slot()),
+            Attr::Ring => Some(self.ring.slot()),
+            _ => None,
+        }
+    }
+}
+
+
+/// Slots of the `mode` channel.
 #[derive(Clone, Copy, Debug)]
 pub enum BeamMode {
     Manual,
@@ -64,16 +68,7 @@ pub enum BeamMode {
     Auto,
 }
 
-impl BeamMode {
-    pub fn byte(&self) -> u8 {
-        match self {
-            BeamMode::Manual => 0,
-            BeamMode::ColorCycle => 159,
-            BeamMode::Auto => 60,
-        }
-    }
-}
-
+/// Slots of the `ring` channel.
 #[derive(Clone, Copy, Debug)]
 pub enum BeamRing {
     Off,
@@ -102,33 +97,41 @@ pub enum BeamRing {
     Raw(u8),
 }
 
+impl BeamMode {
+    /// The `mode` slot.
+    pub fn slot(self) -> Value {
+        Value::Slot(match self {
+            BeamMode::Manual => "manual",
+            BeamMode::ColorCycle => "color_cycle",
+            BeamMode::Auto => "auto",
+        })
+    }
+}
+
 impl BeamRing {
-    pub fn byte(&self) -> u8 {
-        match self {
-            BeamRing::Off => 0,
-
-            BeamRing::Red => 4,
-            BeamRing::Green => 22,
-            BeamRing::Blue => 36,
-            BeamRing::Yellow => 56,
-            BeamRing::Purple => 74,
-            BeamRing::Teal => 84,
-            BeamRing::White => 104,
-
-            BeamRing::RedYellow => 116,
-            BeamRing::RedPurple => 128,
-            BeamRing::RedWhite => 140,
-
-            BeamRing::GreenYellow => 156,
-            BeamRing::GreenBlue => 176,
-            BeamRing::GreenWhite => 192,
-
-            BeamRing::BluePurple => 206,
-            BeamRing::BlueTeal => 216,
-            BeamRing::BlueWhite => 242,
-
-            BeamRing::Cycle => 248,
-            BeamRing::Raw(i) => *i,
-        }
+    /// The `ring` slot.
+    pub fn slot(self) -> Value {
+        let name = match self {
+            BeamRing::Off => "off",
+            BeamRing::Red => "red",
+            BeamRing::Green => "green",
+            BeamRing::Blue => "blue",
+            BeamRing::Yellow => "yellow",
+            BeamRing::Purple => "purple",
+            BeamRing::Teal => "teal",
+            BeamRing::White => "white",
+            BeamRing::RedYellow => "red_yellow",
+            BeamRing::RedPurple => "red_purple",
+            BeamRing::RedWhite => "red_white",
+            BeamRing::GreenYellow => "green_yellow",
+            BeamRing::GreenBlue => "green_blue",
+            BeamRing::GreenWhite => "green_white",
+            BeamRing::BluePurple => "blue_purple",
+            BeamRing::BlueTeal => "blue_teal",
+            BeamRing::BlueWhite => "blue_white",
+            BeamRing::Cycle => "cycle",
+            BeamRing::Raw(i) => return Value::Raw(i),
+        };
+        Value::Slot(name)
     }
 }
@@ -3,6 +3,8 @@ use stagebridge::num::Float;
 
 use crate::ColorMode;
 
+use super::{profile, Attr, Attributes, Value};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Laser {
     pub active: bool,
@@ -38,20 +40,28 @@ impl std::default::Default for Laser {
 }
 
 impl DMXDevice for Laser {
-    fn size(&self) -> usize { 10 }
+    fn size(&self) -> usize { profile("laser").size }
 
-    #[rustfmt::skip]
     fn encode(&self, buffer: &mut [u8]) {
-        buffer[0] = if self.active { 64 } else { 0 };
-        buffer[1] = self.pattern.byte();
-        buffer[2] = self.rotate.lerp_byte(0..127);
-        buffer[3] = self.yflip.lerp_byte(0..127);
-        buffer[4] = self.xflip.lerp_byte(0..127);
-        buffer[5] = self.x.lerp_byte(0..127);
-        buffer[6] = self.y.lerp_byte(0..127);
-        buffer[7] = self.size.lerp_byte(0..63);
-        buffer[8] = self.color.byte();
-        buffer[9] = self.stroke.byte();
+        profile("laser").encode(self, buffer);
+    }
+}
+
+impl Attributes for Laser {
+    fn value(&self, attr: Attr, _head: u8) -> Option<Value> {
+        match attr {
+            Attr::Active => Some(Value::Slot(if self.active { "on" } else { "off" })),
+            Attr::Pattern => Some(self.pattern.slot()),
+            Attr::Rotate => Some(Value::Fr(self.rotate)),
+            Attr::XFlip => Some(Value::Fr(self.xflip)),
+            Attr::YFlip => Some(Value::Fr(self.yflip)),
+            Attr::X => Some(Value::Fr(self.x)),
+            Attr::Y => Some(Value::Fr(self.y)),
+            Attr::Size => Some(Value::Fr(self.size)),
+            Attr::LaserColor => Some(self.color.slot()),
+            Attr::Stroke => Some(self.stroke.slot()),
+            _ => None,
+        }
     }
 }
 
@@ -79,6 +89,7 @@ pub enum LaserStroke {
     Dots(f32),
 }
 
+/// Slots of the `pattern` channel.
 #[derive(Clone, Copy, Debug)]
 pub enum LaserPattern {
     Raw(u8),
@@ -141,101 +152,91 @@ impl LaserColor {
     pub const BLUE: Self = LaserColor::Rgb(false, false, true);
     pub const RGB: Self = LaserColor::Rgb(true, true, true);
 
-    pub fn byte(self) -> u8 {
+    /// The `laser_color` slot.
+    pub fn slot(self) -> Value {
         match self {
-            LaserColor::Raw(i) => i,
-
-            LaserColor::Rgb(r, g, b) => match (r, g, b) {
-                (true, false, false) => 76, // R
-                (false, true, false) => 98, // G
-                (false, false, true) => 116, // B
-
-                (true, true, false) => 86, // RG
-                (true, false, true) => 122, // RB
-                (false, true, true) => 104, // BG
-
-                (true, true, true) => 64, // RGB
-                (false, false, false) => 0, // unused
-            }
-
-            LaserColor::Mix(i) => match i % 7 {
-                0 => 0,
-                1 => 10,
-                2 => 20,
-                3 => 28,
-                4 => 38,
-                5 => 50,
-                6 => 58,
-                _ => 0,
-            }
+            LaserColor::Raw(i) => Value::Raw(i),
+            LaserColor::Rgb(r, g, b) => Value::Slot(match (r, g, b) {
+                (true, false, false) => "r",
+                (false, true, false) => "g",
+                (false, false, true) => "b",
+                (true, true, false) => "rg",
+                (true, false, true) => "rb",
+                (false, true, true) => "gb",
+                (true, true, true) => "rgb",
+                (false, false, false) => "none",
+            }),
+            LaserColor::Mix(i) => Value::Slot(["mix0", "mix1", "mix2", "mix3", "mix4", "mix5", "mix6"][i % 7]),
         }
     }
 }
 
 impl LaserStroke {
-    pub fn byte(self) -> u8 {
+    /// The `stroke` slot, with the channel inverted so 1 is the longest stroke.
+    pub fn slot(self) -> Value {
         match self {
-            LaserStroke::Solid(fr) => fr.inv().lerp_byte(0..127),
-            LaserStroke::Dots(fr) => fr.inv().lerp_byte(128..255),
+            LaserStroke::Solid(fr) => Value::SlotFr("solid", fr),
+            LaserStroke::Dots(fr) => Value::SlotFr("dots", fr),
         }
     }
 }
 
 impl LaserPattern {
-    pub fn byte(self) -> u8 {
-        match self {
-            LaserPattern::Raw(i) => i,
-
-            LaserPattern::Square => 0,
-            LaserPattern::SquareWide => 232,
-            LaserPattern::SquareXWide => 255,
-            LaserPattern::SquareBlock => 224,
-            LaserPattern::Circle => 6,
-            LaserPattern::CircleWide => 82,
-            LaserPattern::CircleDash => 138,
-            LaserPattern::CircleQuad => 144,
-            LaserPattern::CircleCircle => 146,
-            LaserPattern::CircleSquare => 162,
-            LaserPattern::CircleX => 26,
-            LaserPattern::CircleY => 32,
-            LaserPattern::LineX => 12,
-            LaserPattern::LineY => 16,
-            LaserPattern::LineXY => 22,
-            LaserPattern::LineDX => 46,
-            LaserPattern::LineDY => 52,
-            LaserPattern::Line2X => 56,
-            LaserPattern::Line2Y => 62,
-            LaserPattern::LinePenta => 172,
-            LaserPattern::LineStair => 182,
-            LaserPattern::Tri => 36,
-            LaserPattern::TriX => 42,
-            LaserPattern::TriY => 100,
-            LaserPattern::Tri3d => 152,
-            LaserPattern::TriTri => 168,
-            LaserPattern::TriCircle => 214,
-            LaserPattern::TriWing => 218,
-            LaserPattern::TriArch => 224,
-            LaserPattern::Penta => 186,
-            LaserPattern::Squig1 => 66,
-            LaserPattern::Squig2 => 72,
-            LaserPattern::Three => 94,
-            LaserPattern::Two => 112,
-            LaserPattern::One => 116,
-            LaserPattern::Music => 76,
-            LaserPattern::Tree => 86,
-            LaserPattern::Star => 104,
-            LaserPattern::Sin => 108,
-            LaserPattern::Heart => 122,
-            LaserPattern::Elephant => 126,
-            LaserPattern::Apple => 132,
-            LaserPattern::Plus => 156,
-            LaserPattern::PlusOval => 194,
-            LaserPattern::PlusArrow => 196,
-            LaserPattern::PlusDia => 250,
-            LaserPattern::Arrow => 204,
-            LaserPattern::ArrowInvert => 228,
-            LaserPattern::Hourglass1 => 238,
-            LaserPattern::Hourglass2 => 210,
-        }
+    /// The `pattern` slot.
+    pub fn slot(self) -> Value {
+        let name = match self {
+            LaserPattern::Raw(i) => return Value::Raw(i),
+            LaserPattern::Square => "square",
+            LaserPattern::SquareWide => "square_wide",
+            LaserPattern::SquareXWide => "square_x_wide",
+            LaserPattern::SquareBlock => "square_block",
+            LaserPattern::Circle => "circle",
+            LaserPattern::CircleWide => "circle_wide",
+            LaserPattern::CircleDash => "circle_dash",
+            LaserPattern::CircleQuad => "circle_quad",
+            LaserPattern::CircleCircle => "circle_circle",
+            LaserPattern::CircleSquare => "circle_square",
+            LaserPattern::CircleX => "circle_x",
+            LaserPattern::CircleY => "circle_y",
+            LaserPattern::LineX => "line_x",
+            LaserPattern::LineY => "line_y",
+            LaserPattern::LineXY => "line_x_y",
+            LaserPattern::LineDX => "line_d_x",
+            LaserPattern::LineDY => "line_d_y",
+            LaserPattern::Line2X => "line2_x",
+            LaserPattern::Line2Y => "line2_y",
+            LaserPattern::LinePenta => "line_penta",
+            LaserPattern::LineStair => "line_stair",
+            LaserPattern::Tri => "tri",
+            LaserPattern::TriX => "tri_x",
+            LaserPattern::TriY => "tri_y",
+            LaserPattern::Tri3d => "tri3d",
+            LaserPattern::TriTri => "tri_tri",
+            LaserPattern::TriCircle => "tri_circle",
+            LaserPattern::TriWing => "tri_wing",
+            LaserPattern::TriArch => "tri_arch",
+            LaserPattern::Penta => "penta",
+            LaserPattern::Squig1 => "squig1",
+            LaserPattern::Squig2 => "squig2",
+            LaserPattern::Three => "three",
+            LaserPattern::Two => "two",
+            LaserPattern::One => "one",
+            LaserPattern::Music => "music",
+            LaserPattern::Tree => "tree",
+            LaserPattern::Star => "star",
+            LaserPattern::Sin => "sin",
+            LaserPattern::Heart => "heart",
+            LaserPattern::Elephant => "elephant",
+            LaserPattern::Apple => "apple",
+            LaserPattern::Plus => "plus",
+            LaserPattern::PlusOval => "plus_oval",
+            LaserPattern::PlusArrow => "plus_arrow",
+            LaserPattern::PlusDia => "plus_dia",
+            LaserPattern::Arrow => "arrow",
+            LaserPattern::ArrowInvert => "arrow_invert",
+            LaserPattern::Hourglass1 => "hourglass1",
+            LaserPattern::Hourglass2 => "hourglass2",
+        };
+        Value::Slot(name)
     }
 }
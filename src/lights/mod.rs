@@ -8,10 +8,43 @@ mod par; pub use par::*;
 mod bar; pub use bar::*;
 mod strobe; pub use strobe::*;
 mod laser; pub use laser::*;
+mod patch; pub use patch::*;
+mod profile; pub use profile::*;
+
+use serde::{Deserialize, Serialize};
 
 use crate::color::Color;
 
-#[derive(Default)]
+/// A group of fixtures with its own submaster.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Group {
+    Pars,
+    Beams,
+    Bars,
+    Strobe,
+    Spiders,
+}
+
+impl Group {
+    pub const ALL: [Group; 5] = [Group::Pars, Group::Beams, Group::Bars, Group::Strobe, Group::Spiders];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Group::Pars => "pars",
+            Group::Beams => "beams",
+            Group::Bars => "bars",
+            Group::Strobe => "strobe",
+            Group::Spiders => "spiders",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|g| g.name() == name)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct Lights {
     pub pars: [Par; 10],
     pub beams: [Beam; 4],
@@ -23,24 +56,24 @@ pub struct Lights {
 
 impl Lights {
     pub fn write(&self, dmx: &mut DMX) {
-        for (i, par) in self.pars.iter().enumerate() {
-            par.write(dmx, 1 + 8*i);
+        for (par, fixture) in self.pars.iter().zip(&PATCH.pars) {
+            par.write(dmx, fixture.addr);
         }
 
-        for (i, beam) in self.beams.iter().enumerate() {
-            beam.write(dmx, 81 + 15 * i);
+        for (beam, fixture) in self.beams.iter().zip(&PATCH.beams) {
+            beam.write(dmx, fixture.addr);
         }
 
-        self.strobe.write(dmx, 142);
+        self.strobe.write(dmx, PATCH.strobe.addr);
 
-        for (i, bar) in self.bars.iter().enumerate() {
-            bar.write(dmx, 149 + 7 * i);
+        for (bar, fixture) in self.bars.iter().zip(&PATCH.bars) {
+            bar.write(dmx, fixture.addr);
         }
 
-        self.laser.write(dmx, 164);
+        self.laser.write(dmx, PATCH.laser.addr);
 
-        for (i, spider) in self.spiders.iter().enumerate() {
-            spider.write(dmx, 175 + 15*i);
+        for (spider, fixture) in self.spiders.iter().zip(&PATCH.spiders) {
+            spider.write(dmx, fixture.addr);
         }
     }
 
@@ -62,20 +95,56 @@ impl Lights {
         self
     }
 
-    pub fn brightness(&mut self, fr: f32) {
-        for par in &mut self.pars {
-            par.color = par.color.a_mul(fr);
+    /// Crossfade from `self` to `other`.
+    pub fn mix(&self, other: &Self, fr: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * fr;
+        let mut lights = other.clone();
+        for (par, from) in lights.pars.iter_mut().zip(&self.pars) {
+            par.color = from.color.mix(par.color, fr);
         }
-        for beam in &mut self.beams {
-            beam.color = beam.color.a_mul(fr);
+        for (beam, from) in lights.beams.iter_mut().zip(&self.beams) {
+            beam.color = from.color.mix(beam.color, fr);
+            beam.pitch = mix(from.pitch, beam.pitch);
+            beam.yaw = mix(from.yaw, beam.yaw);
         }
-        for bar in &mut self.bars {
-            bar.color = bar.color.a_mul(fr);
+        for (bar, from) in lights.bars.iter_mut().zip(&self.bars) {
+            bar.color = from.color.mix(bar.color, fr);
         }
-        self.strobe.color = self.strobe.color.a_mul(fr);
-        for spider in &mut self.spiders {
-            spider.color0 = spider.color0.a_mul(fr);
-            spider.color1 = spider.color1.a_mul(fr);
+        lights.strobe.color = self.strobe.color.mix(lights.strobe.color, fr);
+        for (spider, from) in lights.spiders.iter_mut().zip(&self.spiders) {
+            spider.color0 = from.color0.mix(spider.color0, fr);
+            spider.color1 = from.color1.mix(spider.color1, fr);
+            spider.pos0 = mix(from.pos0, spider.pos0);
+            spider.pos1 = mix(from.pos1, spider.pos1);
+        }
+        if fr < 0.5 {
+            lights.laser = self.laser;
+        }
+        lights
+    }
+
+    pub fn brightness(&mut self, fr: f32) {
+        for group in Group::ALL {
+            self.group_brightness(group, fr);
+        }
+    }
+
+    pub fn group_brightness(&mut self, group: Group, fr: f32) {
+        match group {
+            Group::Pars => for par in &mut self.pars {
+                par.color = par.color.a_mul(fr);
+            },
+            Group::Beams => for beam in &mut self.beams {
+                beam.color = beam.color.a_mul(fr);
+            },
+            Group::Bars => for bar in &mut self.bars {
+                bar.color = bar.color.a_mul(fr);
+            },
+            Group::Strobe => self.strobe.color = self.strobe.color.a_mul(fr),
+            Group::Spiders => for spider in &mut self.spiders {
+                spider.color0 = spider.color0.a_mul(fr);
+                spider.color1 = spider.color1.a_mul(fr);
+            },
         }
     }
 }
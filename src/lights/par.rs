@@ -3,6 +3,9 @@ use stagebridge::num::Float;
 
 use crate::Color;
 
+use super::{profile, Attributes};
+
+#[derive(Clone, Copy, Debug)]
 pub struct Par {
     pub color: Color,
 }
@@ -16,16 +19,15 @@ impl std::default::Default for Par {
 }
 
 impl DMXDevice for Par {
-    fn size(&self) -> usize { 8 }
+    fn size(&self) -> usize { profile("par").size }
 
     fn encode(&self, buffer: &mut [u8]) {
-        // buffer[0]: ?
-        // buffer[1]: ?
-        // buffer[2]: ?
-        buffer[3] = self.color.a.byte();
-        buffer[4] = self.color.r.byte();
-        buffer[5] = self.color.g.byte();
-        buffer[6] = self.color.b.byte();
-        buffer[7] = self.color.w.byte();
+        profile("par").encode(self, buffer);
+    }
+}
+
+impl Attributes for Par {
+    fn color(&self, _head: u8) -> Option<Color> {
+        Some(self.color)
     }
 }
@@ -0,0 +1,100 @@
+/// Normalized stage position, each axis in 0..1.
+///
+/// x: stage left to stage right, y: floor to truss, z: downstage to upstage.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StagePos {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl StagePos {
+    pub const CENTER: Self = Self::new(0.5, 0.5, 0.5);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self { Self { x, y, z } }
+
+    pub fn dist(self, other: Self) -> f32 {
+        let (dx, dy, dz) = (self.x - other.x, self.y - other.y, self.z - other.z);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Fixture {
+    pub addr: usize,
+    pub pos: StagePos,
+}
+
+const fn fixture(addr: usize, x: f32, y: f32, z: f32) -> Fixture {
+    Fixture { addr, pos: StagePos::new(x, y, z) }
+}
+
+pub struct Patch {
+    pub pars: [Fixture; 10],
+    pub beams: [Fixture; 4],
+    pub strobe: Fixture,
+    pub bars: [Fixture; 2],
+    pub laser: Fixture,
+    pub spiders: [Fixture; 2],
+}
+
+#[rustfmt::skip]
+pub const PATCH: Patch = Patch {
+    pars: [
+        fixture(1,  0.05, 1.0, 0.0),
+        fixture(9,  0.15, 0.0, 0.0),
+        fixture(17, 0.25, 1.0, 0.0),
+        fixture(25, 0.35, 1.0, 0.0),
+        fixture(33, 0.45, 0.0, 0.0),
+        fixture(41, 0.55, 0.0, 0.0),
+        fixture(49, 0.65, 1.0, 0.0),
+        fixture(57, 0.75, 1.0, 0.0),
+        fixture(65, 0.85, 0.0, 0.0),
+        fixture(73, 0.95, 1.0, 0.0),
+    ],
+    beams: [
+        fixture(81,  0.125, 1.0, 0.5),
+        fixture(96,  0.375, 1.0, 0.5),
+        fixture(111, 0.625, 1.0, 0.5),
+        fixture(126, 0.875, 1.0, 0.5),
+    ],
+    strobe: fixture(142, 0.5, 1.0, 0.5),
+    bars: [
+        fixture(149, 0.25, 0.0, 1.0),
+        fixture(156, 0.75, 0.0, 1.0),
+    ],
+    laser: fixture(164, 0.5, 1.0, 1.0),
+    spiders: [
+        fixture(175, 0.25, 1.0, 1.0),
+        fixture(190, 0.75, 1.0, 1.0),
+    ],
+};
+
+/// A scalar field over the stage, used to spread an effect across fixtures by position.
+#[derive(Clone, Copy, Debug)]
+pub enum Shape {
+    LeftRight,
+    RightLeft,
+    BottomTop,
+    TopBottom,
+    FrontBack,
+    CenterOut,
+    OutCenter,
+    Radial(StagePos),
+}
+
+impl Shape {
+    /// Where `pos` falls along this shape, in 0..1.
+    pub fn fr(&self, pos: StagePos) -> f32 {
+        match *self {
+            Shape::LeftRight => pos.x,
+            Shape::RightLeft => 1.0 - pos.x,
+            Shape::BottomTop => pos.y,
+            Shape::TopBottom => 1.0 - pos.y,
+            Shape::FrontBack => pos.z,
+            Shape::CenterOut => (pos.x - 0.5).abs() * 2.0,
+            Shape::OutCenter => 1.0 - (pos.x - 0.5).abs() * 2.0,
+            Shape::Radial(center) => (pos.dist(center) / 3f32.sqrt()).min(1.0),
+        }
+    }
+}
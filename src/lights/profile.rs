@@ -0,0 +1,282 @@
+//! Fixture profiles: which attribute each DMX channel carries and how values
+//! are encoded, loaded from TOML so a new fixture doesn't need code.
+//!
+//! The built-in profiles live in `profiles/`. `MSL_PROFILES=dir` loads every
+//! `*.toml` in `dir` as well, replacing built-in profiles with the same name,
+//! e.g. to patch a rental par in place of ours. A replacement must have the
+//! same number of channels, since the patch addresses are fixed.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use stagebridge::num::Float;
+
+use crate::color::Color;
+use crate::config;
+
+const BUILTIN: [&str; 6] = [
+    include_str!("profiles/par.toml"),
+    include_str!("profiles/beam.toml"),
+    include_str!("profiles/strobe.toml"),
+    include_str!("profiles/bar.toml"),
+    include_str!("profiles/spider.toml"),
+    include_str!("profiles/laser.toml"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attr {
+    Dimmer,
+    Red,
+    Green,
+    Blue,
+    White,
+    Pan,
+    Tilt,
+    Speed,
+    Mode,
+    Ring,
+    Active,
+    Pattern,
+    Rotate,
+    XFlip,
+    YFlip,
+    X,
+    Y,
+    Size,
+    LaserColor,
+    Stroke,
+}
+
+impl Attr {
+    fn is_color(self) -> bool {
+        matches!(self, Attr::Dimmer | Attr::Red | Attr::Green | Attr::Blue | Attr::White)
+    }
+}
+
+/// A named value on a channel, either one DMX value or a range.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Slot {
+    Value(u8),
+    Range(u8, u8),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Channel {
+    pub offset: usize,
+    pub attr: Attr,
+    /// Which head of a multi-head fixture.
+    #[serde(default)]
+    pub head: u8,
+    /// Output range as fractions of full scale.
+    pub range: Option<(f32, f32)>,
+    /// Output range in DMX values.
+    pub bytes: Option<(u8, u8)>,
+    #[serde(default)]
+    pub invert: bool,
+    /// The low byte of a 16 bit value, with the coarse channel carrying the same attribute.
+    #[serde(default)]
+    pub fine: bool,
+    /// The high byte of a 16 bit value, set on load for a channel with a
+    /// `fine` channel alongside.
+    #[serde(skip)]
+    pub coarse: bool,
+    #[serde(default)]
+    pub slots: BTreeMap<String, Slot>,
+    /// Sent when the fixture has no value for the attribute.
+    #[serde(default)]
+    pub default: u8,
+}
+
+/// A value for one attribute, from a fixture.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Fr(f32),
+    Raw(u8),
+    Slot(&'static str),
+    /// A position within a ranged slot.
+    SlotFr(&'static str, f32),
+}
+
+/// What a fixture struct exposes to the encoder.
+pub trait Attributes {
+    /// Color of head `head`, encoded onto the dimmer and color channels.
+    fn color(&self, _head: u8) -> Option<Color> {
+        None
+    }
+
+    /// Any other attribute.
+    fn value(&self, _attr: Attr, _head: u8) -> Option<Value> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Number of channels.
+    pub size: usize,
+    #[serde(rename = "channel")]
+    pub channels: Vec<Channel>,
+}
+
+impl Profile {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut profile: Self = toml::from_str(text)?;
+        profile.validate()?;
+        profile.pair();
+        Ok(profile)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut profile: Self = config::load_toml(path)?;
+        profile.validate().with_context(|| format!("checking {}", path.display()))?;
+        profile.pair();
+        Ok(profile)
+    }
+
+    /// Every channel must fit in the fixture's footprint.
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(ch) = self.channels.iter().find(|ch| ch.offset >= self.size) {
+            anyhow::bail!("{}: channel {} is past the end of {} channels", self.name, ch.offset, self.size);
+        }
+        Ok(())
+    }
+
+    /// Mark the coarse channel of each `fine` channel.
+    fn pair(&mut self) {
+        let fine = self.channels.iter().filter(|ch| ch.fine).map(|ch| (ch.attr, ch.head)).collect::<Vec<_>>();
+        for ch in self.channels.iter_mut().filter(|ch| !ch.fine) {
+            ch.coarse = fine.contains(&(ch.attr, ch.head));
+        }
+    }
+
+    fn has(&self, attr: Attr, head: u8) -> bool {
+        self.channels.iter().any(|ch| ch.attr == attr && ch.head == head)
+    }
+
+    /// One color channel. Without a white channel, white is shown as equal red,
+    /// green and blue in place of the color, and without a dimmer the color is
+    /// scaled by alpha.
+    fn color(&self, color: Color, attr: Attr, head: u8) -> f32 {
+        let alpha = match self.has(Attr::Dimmer, head) {
+            true => 1.0,
+            false => color.a,
+        };
+        let fold = color.w != 0.0 && !self.has(Attr::White, head);
+        match attr {
+            Attr::Dimmer => color.a,
+            Attr::White => color.w * alpha,
+            _ if fold => color.w * alpha,
+            Attr::Red => color.r * alpha,
+            Attr::Green => color.g * alpha,
+            Attr::Blue => color.b * alpha,
+            _ => 0.0,
+        }
+    }
+
+    fn byte(ch: &Channel, value: Value) -> Option<u8> {
+        let fr = |fr: f32| match ch.invert {
+            true => 1.0 - fr,
+            false => fr,
+        };
+        Some(match value {
+            Value::Raw(byte) => byte,
+            Value::Fr(v) => {
+                let v = fr(v);
+                let v = match (ch.range, ch.bytes) {
+                    (_, Some((lo, hi))) => return Some(v.lerp_byte(lo..hi)),
+                    (Some((lo, hi)), _) => v.lerp(lo..hi),
+                    (None, _) => v,
+                };
+                // Both channels of a 16 bit pair split the same value.
+                let wide = (v.clamp(0.0, 1.0) * 65535.0) as u16;
+                match (ch.coarse, ch.fine) {
+                    (true, _) => (wide >> 8) as u8,
+                    (_, true) => (wide & 0xff) as u8,
+                    _ => v.byte(),
+                }
+            },
+            Value::Slot(name) => match ch.slots.get(name)? {
+                Slot::Value(byte) | Slot::Range(byte, _) => *byte,
+            },
+            Value::SlotFr(name, v) => match ch.slots.get(name)? {
+                Slot::Value(byte) => *byte,
+                Slot::Range(lo, hi) => fr(v).lerp_byte(*lo..*hi),
+            },
+        })
+    }
+
+    pub fn encode(&self, fixture: &dyn Attributes, buffer: &mut [u8]) {
+        for ch in &self.channels {
+            let value = match ch.attr {
+                attr if attr.is_color() => fixture.color(ch.head).map(|c| Value::Fr(self.color(c, attr, ch.head))),
+                attr => fixture.value(attr, ch.head),
+            };
+            buffer[ch.offset] = value.and_then(|v| Self::byte(ch, v)).unwrap_or(ch.default);
+        }
+    }
+}
+
+/// Profiles the patch drives. Each has a built-in profile, and an override
+/// must keep its size so fixtures stay at their patched addresses.
+const PATCHED: [&str; 6] = ["par", "beam", "strobe", "bar", "spider", "laser"];
+
+static PROFILES: OnceLock<BTreeMap<String, Profile>> = OnceLock::new();
+
+/// The built-in profiles, replaced by any in `dir` with the same name.
+pub fn load_all(dir: Option<&Path>) -> anyhow::Result<BTreeMap<String, Profile>> {
+    let mut profiles = BTreeMap::new();
+    for text in BUILTIN {
+        let profile = Profile::parse(text).context("built-in profile")?;
+        profiles.insert(profile.name.clone(), profile);
+    }
+
+    if let Some(dir) = dir {
+        let entries = std::fs::read_dir(dir).with_context(|| format!("reading profiles from {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "toml") {
+                continue;
+            }
+            let profile = Profile::load(&path)?;
+            match profiles.get(&profile.name) {
+                Some(builtin) if builtin.size != profile.size => anyhow::bail!(
+                    "{}: {} has {} channels but is patched with {}",
+                    path.display(), profile.name, profile.size, builtin.size,
+                ),
+                _ => {},
+            }
+            log::info!("Loaded fixture profile {} from {}", profile.name, path.display());
+            profiles.insert(profile.name.clone(), profile);
+        }
+    }
+
+    if let Some(name) = PATCHED.iter().find(|name| !profiles.contains_key(**name)) {
+        anyhow::bail!("no fixture profile {}", name);
+    }
+    Ok(profiles)
+}
+
+/// Load the built-in profiles and any from `MSL_PROFILES`, once at startup
+/// before anything is rendered.
+pub fn load_profiles() -> anyhow::Result<()> {
+    let dir = std::env::var_os("MSL_PROFILES").map(std::path::PathBuf::from);
+    let profiles = load_all(dir.as_deref())?;
+    PROFILES.set(profiles).map_err(|_| anyhow::anyhow!("fixture profiles are already loaded"))
+}
+
+/// The profile called `name`, built in or from `MSL_PROFILES`. Without
+/// `load_profiles` only the built-in profiles are used.
+pub fn profile(name: &str) -> &'static Profile {
+    let profiles = PROFILES.get_or_init(|| load_all(None).expect("built-in profiles"));
+    // load_all checked every name the patch uses
+    &profiles[name]
+}
+
@@ -3,6 +3,8 @@ use stagebridge::num::Float;
 
 use crate::color::Color;
 
+use super::{profile, Attr, Attributes, Value};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Spider {
     // pub mode: SpiderMode,
@@ -31,25 +33,28 @@ impl std::default::Default for Spider {
 }
 
 impl DMXDevice for Spider {
-    fn size(&self) -> usize { 15 }
+    fn size(&self) -> usize { profile("spider").size }
 
     fn encode(&self, buffer: &mut [u8]) {
-        buffer[0] = self.pos0.byte();
-        buffer[1] = self.pos1.byte();
-        buffer[2] = self.color0.a.byte();
-        // buffer[3]: strobe
+        profile("spider").encode(self, buffer);
+    }
+}
 
-        buffer[4] = self.color0.r.byte();
-        buffer[5] = self.color0.g.byte();
-        buffer[6] = self.color0.b.byte();
-        buffer[7] = self.color0.w.byte();
-        buffer[8] = self.color0.r.byte();
-        buffer[9] = self.color0.g.byte();
-        buffer[10] = self.color0.b.byte();
-        buffer[11] = self.color0.w.byte();
-        // buffer[12]: effect preset
-        // buffer[13]: effect speed
-        // buffer[14]: reset
+impl Attributes for Spider {
+    fn color(&self, head: u8) -> Option<Color> {
+        match head {
+            0 => Some(self.color0),
+            1 => Some(self.color1),
+            _ => None,
+        }
+    }
+
+    fn value(&self, attr: Attr, head: u8) -> Option<Value> {
+        match (attr, head) {
+            (Attr::Tilt, 0) => Some(Value::Fr(self.pos0)),
+            (Attr::Tilt, 1) => Some(Value::Fr(self.pos1)),
+            _ => None,
+        }
     }
 }
 
@@ -3,6 +3,8 @@ use stagebridge::num::Float;
 
 use crate::color::Color;
 
+use super::{profile, Attributes};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Strobe {
     // pub mode: StrobeMode,
@@ -21,21 +23,16 @@ impl std::default::Default for Strobe {
 }
 
 impl DMXDevice for Strobe {
-    fn size(&self) -> usize { 6 }
+    fn size(&self) -> usize { profile("strobe").size }
 
     fn encode(&self, buffer: &mut [u8]) {
-        buffer[0] = self.color.a.byte();
-        // buffer[1]: mode
-        if self.color.w == 0.0 {
-            buffer[2] = self.color.r.byte();
-            buffer[3] = self.color.g.byte();
-            buffer[4] = self.color.b.byte();
-        } else {
-            buffer[2] = self.color.w.byte();
-            buffer[3] = self.color.w.byte();
-            buffer[4] = self.color.w.byte();
-        }
-        // buffer[5]: sound control
+        profile("strobe").encode(self, buffer);
+    }
+}
+
+impl Attributes for Strobe {
+    fn color(&self, _head: u8) -> Option<Color> {
+        Some(self.color)
     }
 }
 
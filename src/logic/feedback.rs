@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+
+use stagebridge::midi::device::launch_control_xl::types::{
+    Brightness as CtrlBrightness, Color as CtrlColor,
+};
+use stagebridge::midi::device::launch_control_xl::Output as CtrlOutput;
+
+use crate::context::Context;
+
+/// An LED on the Launch Control XL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CtrlLed {
+    SendA(u8),
+    SendB(u8),
+    Pan(u8),
+    Focus(u8),
+    Control(u8),
+    Device,
+    Mute,
+    Solo,
+    Record,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl CtrlLed {
+    fn output(self, color: CtrlColor, brightness: CtrlBrightness) -> CtrlOutput {
+        match self {
+            CtrlLed::SendA(i) => CtrlOutput::SendA(i, color, brightness),
+            CtrlLed::SendB(i) => CtrlOutput::SendB(i, color, brightness),
+            CtrlLed::Pan(i) => CtrlOutput::Pan(i, color, brightness),
+            CtrlLed::Focus(i) => CtrlOutput::Focus(i, color, brightness),
+            CtrlLed::Control(i) => CtrlOutput::Control(i, color, brightness),
+            CtrlLed::Device => CtrlOutput::Device(color, brightness),
+            CtrlLed::Mute => CtrlOutput::Mute(color, brightness),
+            CtrlLed::Solo => CtrlOutput::Solo(color, brightness),
+            CtrlLed::Record => CtrlOutput::Record(color, brightness),
+            CtrlLed::Up => CtrlOutput::Up(color, brightness),
+            CtrlLed::Down => CtrlOutput::Down(color, brightness),
+            CtrlLed::Left => CtrlOutput::Left(color, brightness),
+            CtrlLed::Right => CtrlOutput::Right(color, brightness),
+        }
+    }
+}
+
+/// The state of every driven LED for one frame. LEDs left out are off.
+pub type CtrlLeds = BTreeMap<CtrlLed, (CtrlColor, CtrlBrightness)>;
+
+/// Sends Launch Control LED changes only.
+#[derive(Default)]
+pub struct CtrlFeedback {
+    sent: CtrlLeds,
+}
+
+impl CtrlFeedback {
+    /// Forget what was sent, e.g. after the controller reconnects.
+    pub fn clear(&mut self) {
+        self.sent.clear();
+    }
+
+    pub async fn send(&mut self, ctx: &Context, leds: CtrlLeds) {
+        if ctx.ctrl().is_none() {
+            self.sent.clear();
+            return;
+        }
+        for (&led, &(color, brightness)) in &leds {
+            if self.sent.get(&led) != Some(&(color, brightness)) {
+                ctx.send_ctrl(led.output(color, brightness)).await;
+            }
+        }
+        for &led in self.sent.keys().filter(|led| !leds.contains_key(led)) {
+            ctx.send_ctrl(led.output(CtrlColor::Red, CtrlBrightness::Off)).await;
+        }
+        self.sent = leds;
+    }
+}
@@ -1,13 +1,13 @@
 use std::ops::{Deref, DerefMut};
 
+use parking_lot::Mutex;
+
 use async_trait::async_trait;
 
 use stagebridge::midi::device::launch_control_xl::types::{
     Brightness as CtrlBrightness, Color as CtrlColor,
 };
-use stagebridge::midi::device::launch_control_xl::{
-    self, Input as CtrlInput, LaunchControlXL, Output as CtrlOutput,
-};
+use stagebridge::midi::device::launch_control_xl::{self, Input as CtrlInput};
 use stagebridge::midi::device::launchpad_x::types::{Color as PadColor, Coord, PaletteColor, Pos};
 use stagebridge::midi::device::launchpad_x::{
     self, Input as PadInput, LaunchpadX, Output as PadOutput,
@@ -16,15 +16,17 @@ use stagebridge::midi::Midi;
 use stagebridge::num::Float;
 
 type Pad = Midi<LaunchpadX>;
-type Ctrl = Midi<LaunchControlXL>;
 
 use crate::color::Color;
 use crate::{lights::*, Pd};
 use crate::{ClockSource, State};
+use crate::motion::{Motion, Spread, Wave};
 
-use crate::fx::{self, ColorFn, ColorOp, LightColorFn, LightColorOp, LightFn, LightOp};
+use crate::fx::{self, ColorOp};
+use crate::mapping::{Action, Button};
+use crate::show::PALETTES;
 
-use super::Logic;
+use super::{CtrlLed, CtrlLeds, Logic};
 
 pub struct Beams {
     pub pattern: BeamPattern,
@@ -36,7 +38,9 @@ pub enum BeamColor {
     Color1,
     Alternate,
     Roll { pd: Pd, duty: f32, offset: f32, alpha: f32 },
+    Sweep { pd: Pd, shape: Shape, duty: f32, spread: f32 },
 }
+#[derive(Clone, Copy, Debug)]
 pub enum BeamPattern {
     Down,
     Out,
@@ -48,6 +52,53 @@ pub enum BeamPattern {
     SnapX { pd: Pd },
     SnapY { pd: Pd },
     Square { pd: Pd },
+    Move { pitch: Motion, yaw: Motion },
+}
+impl BeamPattern {
+    /// The (pitch, yaw) motion for this pattern.
+    pub fn motion(&self) -> (Motion, Motion) {
+        match *self {
+            BeamPattern::Down => (Motion::fixed(0.0), Motion::fixed(0.5)),
+            BeamPattern::Out => (Motion::fixed(0.5), Motion::fixed(0.5)),
+            BeamPattern::SpreadOut => (
+                Motion::fixed(0.0),
+                Motion::fixed(0.5).layout(&[-0.05, -0.02, 0.02, 0.05]),
+            ),
+            BeamPattern::SpreadIn => (
+                Motion::fixed(0.0),
+                Motion::fixed(0.5).layout(&[0.09, 0.07, -0.07, -0.09]),
+            ),
+            BeamPattern::Cross => (
+                Motion::fixed(0.0),
+                Motion::fixed(0.5).layout(&[0.13, 0.13, -0.13, -0.13]),
+            ),
+            BeamPattern::CrissCross => (
+                Motion::fixed(0.0),
+                Motion::fixed(0.5).layout(&[0.08, -0.05, 0.05, -0.08]),
+            ),
+            BeamPattern::SnapY { pd } => (
+                Motion::new(Wave::Square { duty: 0.5 }, pd.mul(4), 0.15, 0.15).spread(Spread::Fan(0.5)),
+                Motion::fixed(0.5),
+            ),
+            BeamPattern::SnapX { pd } => (
+                Motion::new(Wave::Square { duty: 0.5 }, pd.mul(2), 0.15, 0.15),
+                Motion::new(Wave::Square { duty: 0.5 }, pd.mul(4), 0.5, 0.13)
+                    .phase(0.5)
+                    .spread(Spread::Pairs(0.5)),
+            ),
+            BeamPattern::WaveY { pd } => (
+                Motion::new(Wave::Tri, pd.mul(2), 0.15, 0.15).spread(Spread::Fan(0.5)),
+                Motion::fixed(0.5),
+            ),
+            BeamPattern::Square { pd } => (
+                Motion::new(Wave::Square { duty: 0.5 }, pd.mul(4), 0.225, 0.125)
+                    .phase(0.25)
+                    .spread(Spread::Fan(0.5)),
+                Motion::new(Wave::Square { duty: 0.5 }, pd.mul(4), 0.5, 0.08).spread(Spread::Fan(0.5)),
+            ),
+            BeamPattern::Move { pitch, yaw } => (pitch, yaw),
+        }
+    }
 }
 #[async_trait]
 impl Logic for Beams {
@@ -69,93 +120,19 @@ impl Logic for Beams {
     //     }
     // }
 
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
+    fn render(&self, state: &State, lights: &mut Lights) {
         let mut beams = [Beam::default(); 4];
 
-        match self.pattern {
-            BeamPattern::Down => {
-                for beam in &mut beams {
-                    beam.pitch = 0.0;
-                    beam.yaw = 0.5;
-                }
-            },
-            BeamPattern::Out => {
-                for beam in &mut beams {
-                    beam.pitch = 0.5;
-                    beam.yaw = 0.5;
-                }
-            },
-            BeamPattern::SpreadOut => {
-                beams[0].yaw = 0.5 - 0.05;
-                beams[1].yaw = 0.5 - 0.02;
-                beams[2].yaw = 0.5 + 0.02;
-                beams[3].yaw = 0.5 + 0.05;
-            },
-            BeamPattern::SpreadIn => {
-                beams[0].yaw = 0.5 + 0.09;
-                beams[1].yaw = 0.5 + 0.07;
-                beams[2].yaw = 0.5 - 0.07;
-                beams[3].yaw = 0.5 - 0.09;
-            },
-            BeamPattern::Cross => {
-                beams[0].yaw = 0.5 + 0.13;
-                beams[1].yaw = 0.5 + 0.13;
-                beams[2].yaw = 0.5 - 0.13;
-                beams[3].yaw = 0.5 - 0.13;
-            },
-            BeamPattern::CrissCross => {
-                beams[0].yaw = 0.5 + 0.08;
-                beams[1].yaw = 0.5 - 0.05;
-                beams[2].yaw = 0.5 + 0.05;
-                beams[3].yaw = 0.5 - 0.08;
-            },
-            BeamPattern::SnapY { pd } => {
-                for (i, beam) in beams.iter_mut().enumerate() {
-                    beam.yaw = 0.5;
-                    let t = state.phi(pd.mul(4)).square(1.0, 0.5);
-                    beam.pitch = 0.3 * match i % 2 == 0 {
-                        true => t,
-                        false => 1.0 - t,
-                    }
-                }
-            }
-            BeamPattern::SnapX { pd } => {
-                for (i, beam) in beams.iter_mut().enumerate() {
-                    let t = state.phi(pd.mul(4)).negsquare(1.0, 0.5);
-                    beam.yaw = 0.5 + 0.13 * match i > 1 {
-                        true => t,
-                        false => -t,
-                    };
-                    beam.pitch = 0.3 * state.phi(pd.mul(2)).square(1.0, 0.5);
-                }
-            },
-            BeamPattern::WaveY { pd } => {
-                for (i, beam) in beams.iter_mut().enumerate() {
-                    beam.yaw = 0.5;
-                    let t = state.phi(pd.mul(2)).tri(1.0);
-                    beam.pitch = 0.3 * match i % 2 == 0 {
-                        true => t,
-                        false => 1.0 - t,
-                    }
-                }
-            }
-            BeamPattern::Square { pd } => {
-                for (i, beam) in beams.iter_mut().enumerate() {
-                    let t_pitch = state.phi(pd.mul(4)).phase(1.0, 0.25).square(1.0, 0.5);
-                    let t_yaw = match i % 2 == 0 {
-                        true => state.phi(pd.mul(4)).negsquare(1.0, 0.5),
-                        false => state.phi(pd.mul(4)).phase(1.0, 0.5).negsquare(1.0, 0.5)
-                    };
-                    beam.pitch = 0.1 + 0.25 * match i % 2 == 0 {
-                        true => t_pitch,
-                        false => 1.0 - t_pitch,
-                    };
-                    beam.yaw = 0.5 + 0.08 * t_yaw;
-                }
-            }
+        let (pitch, yaw) = self.pattern.motion();
+        let n = beams.len();
+        for (i, beam) in beams.iter_mut().enumerate() {
+            beam.pitch = pitch.at(state, i, n);
+            beam.yaw = yaw.at(state, i, n);
         }
 
-        for (i, beam) in beams.iter_mut().enumerate() {
+        // Rolls are phased from the first fixture so they keep their timing against the beat.
+        let first = PATCH.beams[0].pos;
+        for (i, (beam, fixture)) in beams.iter_mut().zip(&PATCH.beams).enumerate() {
             beam.ring = self.ring;
             beam.color = match self.color {
                 BeamColor::Color0 => state.color0(),
@@ -166,9 +143,14 @@ impl Logic for Beams {
                 },
                 BeamColor::Roll { pd, duty, offset, alpha } => {
                     let t = state.phi(pd);
-                    let a = t.phase(1.0, offset + 0.25 * i as f32).square(1.0, duty);
+                    let a = t.phase(1.0, offset + fixture.pos.x - first.x).square(1.0, duty);
                     state.color1().a(a * alpha)
-                }
+                },
+                BeamColor::Sweep { pd, shape, duty, spread } => {
+                    let t = state.phi(pd);
+                    let a = t.phase(1.0, spread * shape.fr(fixture.pos)).square(1.0, duty);
+                    state.color1().a(a)
+                },
             };
         }
 
@@ -200,6 +182,25 @@ pub enum LaserPos {
     Still,
     Rotate { pd: Pd },
     WaveY { pd: Pd },
+    Move { axis: LaserAxis, motion: Motion },
+}
+#[derive(Clone, Copy, Debug)]
+pub enum LaserAxis {
+    Rotate,
+    X,
+    Y,
+    Size,
+}
+impl LaserPos {
+    /// The axis and motion for this position, if it moves.
+    pub fn motion(&self) -> Option<(LaserAxis, Motion)> {
+        match *self {
+            LaserPos::Still => None,
+            LaserPos::Rotate { pd } => Some((LaserAxis::Rotate, Motion::new(Wave::Saw, pd, 0.5, 0.5))),
+            LaserPos::WaveY { pd } => Some((LaserAxis::Y, Motion::new(Wave::Saw, pd, 0.5, 0.5))),
+            LaserPos::Move { axis, motion } => Some((axis, motion)),
+        }
+    }
 }
 #[async_trait]
 impl Logic for Lasers {
@@ -220,26 +221,32 @@ impl Logic for Lasers {
     //     }
     // }
 
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
+    fn render(&self, state: &State, lights: &mut Lights) {
         let mut laser = self.laser.clone();
 
-        match self.pos {
-            LaserPos::Rotate { pd } => laser.rotate = state.phi(pd),
-            LaserPos::WaveY { pd } => laser.y = state.phi(pd),
-            LaserPos::Still => {},
+        if let Some((axis, motion)) = self.pos.motion() {
+            let fr = motion.at(state, 0, 1);
+            match axis {
+                LaserAxis::Rotate => laser.rotate = fr,
+                LaserAxis::X => laser.x = fr,
+                LaserAxis::Y => laser.y = fr,
+                LaserAxis::Size => laser.size = fr,
+            }
         }
 
         lights.laser = laser;
+    }
 
-        // ctrl.send(CtrlOutput::Focus(
-        //     0,
-        //     CtrlColor::Red,
-        //     match self.laser.active {
-        //         true => CtrlBrightness::High,
-        //         false => CtrlBrightness::Off,
-        //     },
-        // ))
-        // .await;
+    fn action(&mut self, _: &mut State, action: Action) {
+        if let Action::Button(Button::Laser) = action {
+            self.laser.active = !self.laser.active;
+        }
+    }
+
+    fn leds(&self, _: &State, leds: &mut CtrlLeds) {
+        if self.laser.active {
+            leds.insert(CtrlLed::Focus(5), (CtrlColor::Red, CtrlBrightness::High));
+        }
     }
 
 }
@@ -284,13 +291,17 @@ pub enum ParColor {
     StrobeAlt1 { pd: Pd, duty: f32 },
     StrobeRoll0 { pd: Pd, duty: f32, offset: f32 },
     StrobeRoll1 { pd: Pd, duty: f32, offset: f32 },
+    Wave { pd: Pd, shape: Shape, spread: f32 },
+    Sweep { pd: Pd, shape: Shape, duty: f32, spread: f32 },
 }
 #[async_trait]
 impl Logic for Pars {
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
+    fn render(&self, state: &State, lights: &mut Lights) {
         let color0 = state.color0();
         let color1 = state.color1();
-        for (i, par) in lights.pars.iter_mut().enumerate() {
+        let first = PATCH.pars[0].pos;
+        for (i, (par, fixture)) in lights.pars.iter_mut().zip(&PATCH.pars).enumerate() {
+            let pos = fixture.pos;
             par.color = match self.color {
                 ParColor::Off => Color::OFF,
                 ParColor::Color0 => color0,
@@ -299,48 +310,48 @@ impl Logic for Pars {
                     true => color0,
                     false => color1,
                 },
-                ParColor::UpDown => match i {
-                    0 => color1,
-                    1 => color0,
-                    2..=3 => color1,
-                    4..=5 => color0,
-                    6..=7 => color1,
-                    8 => color0,
-                    9 => color1,
-                    _ => unreachable!(),
+                ParColor::UpDown => match pos.y > 0.5 {
+                    true => color1,
+                    false => color0,
                 },
-                ParColor::Spotlight => match i {
-                    3 => color1,
-                    6 => color1,
-                    _ => color0,
+                // The top pars either side of center, wherever they hang.
+                ParColor::Spotlight => match pos.y > 0.5 && Shape::CenterOut.fr(pos) < 0.4 {
+                    true => color1,
+                    false => color0,
                 },
                 ParColor::Roll { pd, ofs } => {
-                    let i = match state.phi(pd.mul(2)).bsquare(1.0, 0.5) {
-                        true => i,
-                        false => 10 - i,
+                    let shape = match state.phi(pd.mul(2)).bsquare(1.0, 0.5) {
+                        true => Shape::LeftRight,
+                        false => Shape::RightLeft,
                     };
-                    state.color0_phase(pd, ofs + 0.1 * i as f32)
+                    state.color0_phase(pd, ofs + shape.fr(pos) - shape.fr(first))
                 },
                 ParColor::StrobeAlt0 { pd, duty } => {
                     let t = state.phi(pd);
-                    let offset = if i > 4 { 0.5 } else { 0.0 };
+                    let offset = if pos.x > 0.5 { 0.5 } else { 0.0 };
                     let a = t.phase(1.0, offset).square(1.0, duty);
                     state.color0().a(a)
                 },
                 ParColor::StrobeAlt1 { pd, duty } => {
                     let t = state.phi(pd);
-                    let offset = if i > 4 { 0.5 } else { 0.0 };
+                    let offset = if pos.x > 0.5 { 0.5 } else { 0.0 };
                     let a = t.phase(1.0, offset).square(1.0, duty);
                     state.color1().a(a)
                 },
                 ParColor::StrobeRoll0 { pd, duty, offset } => {
                     let t = state.phi(pd);
-                    let a = t.phase(1.0, offset + 0.1 * i as f32).square(1.0, duty);
+                    let a = t.phase(1.0, offset + pos.x - first.x).square(1.0, duty);
                     state.color0().a(a)
                 },
                 ParColor::StrobeRoll1 { pd, duty, offset } => {
                     let t = state.phi(pd);
-                    let a = t.phase(1.0, offset + 0.1 * i as f32).square(1.0, duty);
+                    let a = t.phase(1.0, offset + pos.x - first.x).square(1.0, duty);
+                    state.color1().a(a)
+                },
+                ParColor::Wave { pd, shape, spread } => state.color0_phase(pd, spread * shape.fr(pos)),
+                ParColor::Sweep { pd, shape, duty, spread } => {
+                    let t = state.phi(pd);
+                    let a = t.phase(1.0, spread * shape.fr(pos)).square(1.0, duty);
                     state.color1().a(a)
                 },
             };
@@ -373,15 +384,16 @@ pub enum BarColor {
 }
 #[async_trait]
 impl Logic for Bars {
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
-        for (i, bar) in lights.bars.iter_mut().enumerate() {
+    fn render(&self, state: &State, lights: &mut Lights) {
+        let first = PATCH.bars[0].pos;
+        for (bar, fixture) in lights.bars.iter_mut().zip(&PATCH.bars) {
             bar.color = match self.color {
                 BarColor::Off => Color::OFF,
                 BarColor::Color0 => state.color0(),
                 BarColor::Color1 => state.color1(),
                 BarColor::Roll { pd, duty, offset } => {
                     let t = state.phi(pd);
-                    let a = t.phase(1.0, offset + 0.5 * i as f32).square(1.0, duty);
+                    let a = t.phase(1.0, offset + fixture.pos.x - first.x).square(1.0, duty);
                     state.color1().a(a)
                 }
             };
@@ -418,12 +430,37 @@ pub enum SpiderPattern {
     Wave { pd: Pd },
     Alternate { pd: Pd },
     Snap { pd: Pd },
+    Move { pos0: Motion, pos1: Motion },
+}
+impl SpiderPattern {
+    /// The (pos0, pos1) motion for this pattern.
+    pub fn motion(&self) -> (Motion, Motion) {
+        match *self {
+            SpiderPattern::Up => (Motion::fixed(0.0), Motion::fixed(0.52)),
+            SpiderPattern::Down => (Motion::fixed(0.67), Motion::fixed(0.52)),
+            SpiderPattern::Wave { pd } => (
+                Motion::new(Wave::Tri, pd.mul(2), 0.5, 0.5),
+                Motion::new(Wave::Tri, pd.mul(2), 0.5, 0.5).phase(0.5),
+            ),
+            SpiderPattern::Alternate { pd } => {
+                let motion = Motion::new(Wave::Tri, pd.mul(2), 0.5, 0.5).spread(Spread::Fan(0.5));
+                (motion, motion)
+            },
+            SpiderPattern::Snap { pd } => {
+                let motion = Motion::new(Wave::Square { duty: 0.5 }, pd.mul(2), 0.5, 0.5).spread(Spread::Fan(0.5));
+                (motion, motion)
+            },
+            SpiderPattern::Move { pos0, pos1 } => (pos0, pos1),
+        }
+    }
 }
 #[async_trait]
 impl Logic for Spiders {
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
+    fn render(&self, state: &State, lights: &mut Lights) {
         let color0 = state.color0();
         let color1 = state.color1();
+        let (pos0, pos1) = self.pattern.motion();
+        let n = lights.spiders.len();
         for (i, spider) in lights.spiders.iter_mut().enumerate() {
             match self.color {
                 SpiderColor::Off => {
@@ -444,43 +481,8 @@ impl Logic for Spiders {
                 },
             }
 
-            match self.pattern {
-                SpiderPattern::Up => {
-                    spider.pos0 = 0.0;
-                    spider.pos1 = 0.52;
-                },
-                SpiderPattern::Down => {
-                    spider.pos0 = 0.67;
-                    spider.pos1 = 0.52;
-                },
-                SpiderPattern::Wave { pd } => {
-                    let fr = state.phi(pd.mul(2)).tri(1.0);
-                    spider.pos0 = fr;
-                    spider.pos1 = 1.0 - fr;
-                },
-                SpiderPattern::Alternate { pd } => {
-                    let t = state.phi(pd.mul(2));
-                    let t = match i {
-                        0 => t,
-                        1 => t.phase(1.0, 0.5),
-                        _ => unreachable!(),
-                    };
-                    let fr = t.tri(1.0);
-                    spider.pos0 = fr;
-                    spider.pos1 = fr;
-                },
-                SpiderPattern::Snap { pd } => {
-                    let t = state.phi(pd.mul(2));
-                    let t = match i {
-                        0 => t,
-                        1 => t.phase(1.0, 0.5),
-                        _ => unreachable!(),
-                    };
-                    let fr = t.square(1.0, 0.5);
-                    spider.pos0 = fr;
-                    spider.pos1 = fr;
-                },
-            }
+            spider.pos0 = pos0.at(state, i, n);
+            spider.pos1 = pos1.at(state, i, n);
         }
     }
 }
@@ -509,7 +511,7 @@ pub enum StrobeColor {
 }
 #[async_trait]
 impl Logic for Strobes {
-    async fn output(&self, state: &State, lights: &mut Lights, _: &Pad, _: &Ctrl) {
+    fn render(&self, state: &State, lights: &mut Lights) {
         lights.strobe.color = match self.color {
             StrobeColor::Off => Color::OFF,
             StrobeColor::Color0 => state.color0(),
@@ -534,33 +536,105 @@ impl Strobes {
 }
 
 pub struct Pads {
-    pub pattern: PadPattern,
     pub brightness: f32,
+
+    /// Grid position of the active scene.
+    pub active: Option<(u8, u8)>,
+    /// Scenes waiting to fire, e.g. the next cue.
+    pub queued: Vec<(u8, u8)>,
+    /// Leave the grid to another logic, e.g. the sequencer while editing.
+    pub hidden: bool,
+
+    /// What each cell was last set to, so unchanged cells aren't resent.
+    sent: Mutex<[[Option<PaletteColor>; 8]; 8]>,
 }
-#[derive(Clone, Copy, Debug)]
-pub enum PadPattern {
-    Color0,
-    Color1,
+impl Pads {
+    /// The resting color of the cell at (x, y), by scene category.
+    fn cell(&self, state: &State, x: u8, y: u8) -> PaletteColor {
+        match (x, y) {
+            // beat0 and beat1
+            (0..=3, 0) => state.color0().a(1.0).palette(),
+            (_, 0) => state.color1().a(1.0).palette(),
+            // colors, shown as the scene's color0
+            (_, 1 | 2) => PALETTES[y as usize - 1][x as usize].0.apply(state).palette(),
+            // low and low+move
+            (0..=3, 3 | 4) => PaletteColor::Blue,
+            // build
+            (0..=3, 5 | 6) => PaletteColor::Yellow,
+            // break
+            (_, 3 | 4) => PaletteColor::Violet,
+            // drop
+            (_, 5 | 6) => PaletteColor::Red,
+            // lasers
+            (4..=7, 7) => PaletteColor::Lime,
+            _ => PaletteColor::Off,
+        }
+    }
 }
+
 #[async_trait]
 impl Logic for Pads {
-    async fn output(&self, state: &State, _: &mut Lights, pad: &Pad, _: &Ctrl) {
-        let color0 = state.color0();
-        let color1 = state.color1();
-        match self.pattern {
-            PadPattern::Color0 => pad.send(PadOutput::ClearColor(color0.into())).await,
-            PadPattern::Color1 => pad.send(PadOutput::ClearColor(color1.into())).await,
-        };
+    async fn feedback(&self, state: &State, pad: &Pad) {
+        if self.hidden {
+            *self.sent.lock() = [[None; 8]; 8];
+            return;
+        }
+
+        let beat = state.phi(Pd(1, 1)).bsquare(1.0, 0.25);
+        let blink = state.phi(Pd(1, 2)).bsquare(1.0, 0.5);
+
+        let mut updates = vec![];
+        {
+            let mut sent = self.sent.lock();
+            for y in 0..8 {
+                for x in 0..8 {
+                    let color = self.cell(state, x, y);
+                    let color = if self.active == Some((x, y)) {
+                        match beat {
+                            true => PaletteColor::White,
+                            false => color,
+                        }
+                    } else if self.queued.contains(&(x, y)) {
+                        match blink {
+                            true => color,
+                            false => PaletteColor::Off,
+                        }
+                    } else {
+                        color
+                    };
+
+                    let cell = &mut sent[y as usize][x as usize];
+                    if *cell != Some(color) {
+                        *cell = Some(color);
+                        updates.push((x, y, color));
+                    }
+                }
+            }
+        }
+
+        for (x, y, color) in updates {
+            pad.send(PadOutput::Light(Coord(x as _, y as _).into(), color)).await;
+        }
     }
 }
 impl Pads {
     pub fn new() -> Self {
         Self {
-            pattern: PadPattern::Color0,
             brightness: 1.0,
+
+            active: None,
+            queued: vec![],
+            hidden: false,
+
+            sent: Mutex::new([[None; 8]; 8]),
         }
     }
 
+    /// Forget what the grid shows, e.g. after the Launchpad reconnects.
+    pub fn resend(&self) {
+        *self.sent.lock() = [[None; 8]; 8];
+    }
+
     pub fn reset(&mut self) {
         *self = Self::new();
     }
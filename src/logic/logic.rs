@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 
-use stagebridge::midi::device::launch_control_xl::{
-    self, Input as CtrlInput, LaunchControlXL, Output as CtrlOutput,
+use stagebridge::midi::device::launch_control_xl::types::{
+    Brightness as CtrlBrightness, Color as CtrlColor,
 };
 use stagebridge::midi::device::launchpad_x::types::{Color as PadColor, Coord, PaletteColor, Pos};
 use stagebridge::midi::device::launchpad_x::{
@@ -11,49 +11,68 @@ use stagebridge::midi::Midi;
 use stagebridge::num::Float;
 
 type Pad = Midi<LaunchpadX>;
-type Ctrl = Midi<LaunchControlXL>;
 
 use crate::color::Color;
 use crate::lights::Lights;
+use crate::mapping::{Action, Button};
 use crate::{State, Pd};
 
+use super::{CtrlLed, CtrlLeds};
+
 #[async_trait]
 pub trait Logic: Sync {
-    fn pad(&mut self, _state: &mut State, _input: PadInput) {}
-    fn ctrl(&mut self, _state: &mut State, _input: CtrlInput) {}
-    async fn output(&self, state: &State, lights: &mut Lights, pad: &Pad, ctrl: &Ctrl);
+    /// Controller input, from the default Novation layout or a mapping file.
+    fn action(&mut self, _state: &mut State, _action: Action) {}
+    /// Draw this logic's part of the frame. Runs whether or not any controllers are connected.
+    fn render(&self, _state: &State, _lights: &mut Lights) {}
+    /// Launch Control LEDs, sent only while it's connected.
+    fn leds(&self, _state: &State, _leds: &mut CtrlLeds) {}
+    /// Launchpad feedback, only called while it's connected.
+    async fn feedback(&self, _state: &State, _pad: &Pad) {}
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum ClockSource {
     Osc,
     Static { bpm: f32 },
+    Audio,
+    /// Follow external timecode, with the phrase counted from 0:00:00:00 at `bpm`.
+    Timecode { bpm: f32 },
 }
 
 pub struct Time {
-    pub source: ClockSource
+    pub source: ClockSource,
+    pub audio: bool,
+    pub timecode: bool,
 }
 #[async_trait]
 impl Logic for Time {
-    fn pad(&mut self, _: &mut State, input: PadInput) {
-        if let PadInput::Capture(true) = input {
-            self.source = match self.source {
-                ClockSource::Osc => ClockSource::Static { bpm: 120.0 },
-                ClockSource::Static { .. } => ClockSource::Osc,
-            }
+    fn action(&mut self, state: &mut State, action: Action) {
+        match action {
+            Action::Button(Button::ClockSource) => self.next_source(),
+            Action::PhiMul(mul) => state.phi_mul = mul,
+            _ => {},
         }
     }
 
-    fn ctrl(&mut self, state: &mut State, input: CtrlInput) {
-        match input {
-            CtrlInput::Mute(true) => state.phi_mul = 0.5,
-            CtrlInput::Solo(true) => state.phi_mul = 1.0,
-            CtrlInput::Record(true) => state.phi_mul = 2.0,
-            _ => {},
-        }
+    fn leds(&self, state: &State, leds: &mut CtrlLeds) {
+        let selected = |mul: f32| match state.phi_mul == mul {
+            true => (CtrlColor::Yellow, CtrlBrightness::High),
+            false => (CtrlColor::Yellow, CtrlBrightness::Low),
+        };
+        leds.insert(CtrlLed::Mute, selected(0.5));
+        leds.insert(CtrlLed::Solo, selected(1.0));
+        leds.insert(CtrlLed::Record, selected(2.0));
+
+        leds.insert(CtrlLed::Control(5), match self.source {
+            ClockSource::Osc => (CtrlColor::Green, CtrlBrightness::High),
+            ClockSource::Static { .. } => (CtrlColor::Green, CtrlBrightness::Low),
+            ClockSource::Audio => (CtrlColor::Amber, CtrlBrightness::High),
+            ClockSource::Timecode { .. } => (CtrlColor::Red, CtrlBrightness::High),
+        });
     }
 
-    async fn output(&self, state: &State, _: &mut Lights, pad: &Pad, _: &Ctrl) {
+    async fn feedback(&self, state: &State, pad: &Pad) {
         pad.send(PadOutput::Light(
             Coord(8, 8).into(),
             match state.phi(Pd(1, 1)).bsquare(1.0, 0.05) {
@@ -67,16 +86,32 @@ impl Logic for Time {
             match &self.source {
                 ClockSource::Osc => PaletteColor::White,
                 ClockSource::Static { .. } => PaletteColor::Off,
+                ClockSource::Audio => PaletteColor::Cyan,
+                ClockSource::Timecode { .. } => PaletteColor::Magenta,
             },
         ))
         .await;
     }
 }
 impl Time {
+    fn next_source(&mut self) {
+        self.source = match self.source {
+            ClockSource::Osc => ClockSource::Static { bpm: 120.0 },
+            ClockSource::Static { .. } if self.audio => ClockSource::Audio,
+            ClockSource::Static { bpm } if self.timecode => ClockSource::Timecode { bpm },
+            ClockSource::Static { .. } => ClockSource::Osc,
+            ClockSource::Audio if self.timecode => ClockSource::Timecode { bpm: 120.0 },
+            ClockSource::Audio => ClockSource::Osc,
+            ClockSource::Timecode { .. } => ClockSource::Osc,
+        }
+    }
+
     pub fn new() -> Self {
         Self {
             source: ClockSource::Osc,
             // source: ClockSource::Static { bpm: 120.0 }
+            audio: false,
+            timecode: false,
         }
     }
 }
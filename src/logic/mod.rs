@@ -3,3 +3,15 @@ pub use logic::*;
 
 mod lights;
 pub use lights::*;
+
+mod sequencer;
+pub use sequencer::*;
+
+mod playback;
+pub use playback::*;
+
+mod stages;
+pub use stages::*;
+
+mod feedback;
+pub use feedback::*;
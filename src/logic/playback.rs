@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use anyhow::{bail, Context as _};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use stagebridge::midi::device::launch_control_xl::types::{
+    Brightness as CtrlBrightness, Color as CtrlColor,
+};
+
+use crate::config;
+use crate::mapping::{Action, Button};
+use crate::State;
+
+use super::{CtrlLed, CtrlLeds, Logic};
+
+/// When a cue fires. Cues without one wait for GO.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CueAt {
+    /// 1-based bar and beat from the start of playback, 4 beats to the bar.
+    Bar { bar: u32, beat: f32 },
+    /// Seconds of `state.t`, e.g. timecode.
+    Time { time: f32 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cue {
+    #[serde(default)]
+    pub name: String,
+    pub scene: (u8, u8),
+    pub at: Option<CueAt>,
+    /// Crossfade from the previous cue, in beats.
+    #[serde(default)]
+    pub fade: f32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CueList {
+    #[serde(rename = "cue")]
+    pub cues: Vec<Cue>,
+}
+
+impl CueList {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let list: Self = config::load_toml(path)?;
+        list.validate().with_context(|| format!("checking {}", path.display()))?;
+        Ok(list)
+    }
+
+    /// Every cue's scene must be on the 8x8 grid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (i, cue) in self.cues.iter().enumerate() {
+            let (x, y) = cue.scene;
+            if x > 7 || y > 7 {
+                bail!("cue {} {:?}: scene ({}, {}) is off the grid", i + 1, cue.name, x, y);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackMode {
+    Stopped,
+    /// Waiting for the next phrase to start.
+    Armed,
+    Running,
+    Paused,
+}
+
+/// Steps through a cue list against the clock.
+///
+/// GO arms playback from stopped, starting on the next phrase, and otherwise
+/// fires the next cue immediately. BACK refires the previous cue.
+pub struct Playback {
+    pub list: CueList,
+    pub mode: PlaybackMode,
+    /// Index of the next cue to fire.
+    pub next: usize,
+    /// Beats since playback started.
+    pub beats: f32,
+
+    last_phi: f32,
+    go: bool,
+    back: bool,
+}
+
+impl Playback {
+    pub fn new(list: CueList) -> Self {
+        Self {
+            list,
+            mode: PlaybackMode::Stopped,
+            next: 0,
+            beats: 0.0,
+
+            last_phi: 0.0,
+            go: false,
+            back: false,
+        }
+    }
+
+    pub fn go(&mut self) {
+        self.go = true;
+    }
+
+    pub fn back(&mut self) {
+        self.back = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.mode = match self.mode {
+            PlaybackMode::Running => PlaybackMode::Paused,
+            PlaybackMode::Paused => PlaybackMode::Running,
+            mode => mode,
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.mode = PlaybackMode::Stopped;
+        self.next = 0;
+        self.beats = 0.0;
+    }
+
+    /// The scene of the next cue, if playback is going.
+    pub fn queued(&self) -> Option<(u8, u8)> {
+        match self.mode {
+            PlaybackMode::Stopped => None,
+            _ => self.list.cues.get(self.next).map(|cue| cue.scene),
+        }
+    }
+
+    fn due(&self, state: &State, cue: &Cue) -> bool {
+        match cue.at {
+            Some(CueAt::Bar { bar, beat }) => {
+                self.beats >= (bar.saturating_sub(1) * 4) as f32 + (beat - 1.0)
+            },
+            Some(CueAt::Time { time }) => state.t >= time,
+            None => false,
+        }
+    }
+
+    /// Advance against the clock, returning the cue to fire this frame, if any.
+    pub fn poll(&mut self, state: &State) -> Option<Cue> {
+        let d = state.phi - self.last_phi;
+        let wrapped = d < -8.0;
+        let d = if wrapped { d + 16.0 } else { d };
+        self.last_phi = state.phi;
+
+        if std::mem::take(&mut self.back) && self.next >= 2 {
+            self.next -= 2;
+            return self.fire();
+        }
+
+        let go = std::mem::take(&mut self.go);
+        match self.mode {
+            PlaybackMode::Stopped if go && !self.list.cues.is_empty() => {
+                log::info!("Playback armed for the next phrase");
+                self.mode = PlaybackMode::Armed;
+                None
+            },
+            PlaybackMode::Armed if wrapped => {
+                self.mode = PlaybackMode::Running;
+                self.next = 0;
+                self.beats = state.phi;
+                self.fire()
+            },
+            PlaybackMode::Running => {
+                self.beats += d.max(0.0);
+                let cue = self.list.cues.get(self.next)?;
+                if go || self.due(state, cue) {
+                    self.fire()
+                } else {
+                    None
+                }
+            },
+            PlaybackMode::Paused if go => self.fire(),
+            _ => None,
+        }
+    }
+
+    fn fire(&mut self) -> Option<Cue> {
+        let cue = self.list.cues.get(self.next)?.clone();
+        log::info!("Cue {} {:?} at beat {:.2}", self.next, cue.name, self.beats);
+        self.next += 1;
+        if self.next >= self.list.cues.len() {
+            self.mode = PlaybackMode::Stopped;
+        }
+        Some(cue)
+    }
+}
+
+#[async_trait]
+impl Logic for Playback {
+    fn action(&mut self, _: &mut State, action: Action) {
+        match action {
+            Action::Button(Button::Go) => self.go(),
+            Action::Button(Button::Back) => self.back(),
+            Action::Button(Button::Pause) => self.pause(),
+            _ => {},
+        }
+    }
+
+    fn leds(&self, _: &State, leds: &mut CtrlLeds) {
+        let brightness = match self.mode {
+            PlaybackMode::Stopped => return,
+            PlaybackMode::Armed | PlaybackMode::Paused => CtrlBrightness::Low,
+            PlaybackMode::Running => CtrlBrightness::High,
+        };
+        leds.insert(CtrlLed::Device, (CtrlColor::Green, brightness));
+    }
+}
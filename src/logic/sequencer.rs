@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+
+use stagebridge::midi::device::launchpad_x::types::{Color as PadColor, Coord, PaletteColor, Pos};
+use stagebridge::midi::device::launchpad_x::{
+    self, Input as PadInput, LaunchpadX, Output as PadOutput,
+};
+use stagebridge::midi::Midi;
+use stagebridge::num::Float;
+
+type Pad = Midi<LaunchpadX>;
+
+use crate::color::Color;
+use crate::config;
+use crate::lights::Lights;
+use crate::mapping::{Action, Button, Fader};
+use crate::{State, Pd};
+
+use super::Logic;
+
+/// Number of sequenced fixtures: pars, beams, bars, spiders, then the strobe.
+pub const FIXTURES: usize = 10 + 4 + 2 + 2 + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepColor {
+    Off,
+    Color0,
+    Color1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub color: StepColor,
+    pub alpha: f32,
+}
+
+/// One fixture's value at one step. `None` leaves the fixture to the other logic.
+pub type Step = [Option<Cell>; FIXTURES];
+
+#[derive(Clone, Debug)]
+pub struct Sequence {
+    pub pd: Pd,
+    /// Delay of every odd step, 0..0.5 of a step pair.
+    pub swing: f32,
+    /// Fraction of each step spent crossfading into the next.
+    pub fade: f32,
+    pub steps: Vec<Step>,
+}
+
+impl Sequence {
+    pub fn new(len: usize) -> Self {
+        Self {
+            pd: Pd(1, 4),
+            swing: 0.0,
+            fade: 0.0,
+            steps: vec![[None; FIXTURES]; len],
+        }
+    }
+
+    /// The current step, the next step, and how far to fade between them.
+    pub fn position(&self, state: &State) -> (usize, usize, f32) {
+        let len = self.steps.len();
+        let t = state.phi(self.pd.mul(len)) * len as f32;
+
+        let pair = (t / 2.0).floor() * 2.0;
+        let u = t - pair;
+        let b = 1.0 + self.swing.clamp(0.0, 0.5);
+        let (i, fr) = match u < b {
+            true => (pair as usize, u / b),
+            false => (pair as usize + 1, (u - b) / (2.0 - b)),
+        };
+
+        let i = i % len;
+        let fade = match self.fade > 0.0 {
+            true => ((fr - (1.0 - self.fade)) / self.fade).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        (i, (i + 1) % len, fade)
+    }
+}
+
+/// Sequences as saved, with only the programmed cells:
+///
+/// ```toml
+/// [[sequence]]
+/// scene = [6, 6]
+/// pd = [1, 4]
+/// swing = 0.1
+/// fade = 0.0
+/// len = 16
+///
+/// [[sequence.cell]]
+/// step = 0
+/// fixture = 3
+/// color = "color0"
+/// alpha = 1.0
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SequenceFile {
+    #[serde(default, rename = "sequence")]
+    pub sequences: Vec<SavedSequence>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedSequence {
+    pub scene: (u8, u8),
+    pub pd: Pd,
+    #[serde(default)]
+    pub swing: f32,
+    #[serde(default)]
+    pub fade: f32,
+    pub len: usize,
+    #[serde(default, rename = "cell")]
+    pub cells: Vec<SavedCell>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SavedCell {
+    pub step: usize,
+    pub fixture: usize,
+    pub color: StepColor,
+    pub alpha: f32,
+}
+
+impl SequenceFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file: Self = config::load_toml(path)?;
+        file.validate().with_context(|| format!("checking {}", path.display()))?;
+        Ok(file)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let text = toml::to_string(self)?;
+        std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for seq in &self.sequences {
+            let (x, y) = seq.scene;
+            if x > 7 || y > 7 {
+                bail!("sequence ({}, {}) is off the grid", x, y);
+            }
+            if seq.len == 0 || seq.pd.0 == 0 || seq.pd.1 == 0 {
+                bail!("sequence ({}, {}) needs a length and a nonzero pd", x, y);
+            }
+            if !(0.0..=0.5).contains(&seq.swing) || !(0.0..=1.0).contains(&seq.fade) {
+                bail!("sequence ({}, {}): swing must be 0-0.5 and fade 0-1", x, y);
+            }
+            for cell in &seq.cells {
+                if cell.step >= seq.len || cell.fixture >= FIXTURES || !(0.0..=1.0).contains(&cell.alpha) {
+                    bail!("sequence ({}, {}): bad cell {:?}", x, y, cell);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from_sequences(sequences: &HashMap<(u8, u8), Sequence>) -> Self {
+        let mut sequences = sequences
+            .iter()
+            .map(|(&scene, seq)| SavedSequence {
+                scene,
+                pd: seq.pd,
+                swing: seq.swing,
+                fade: seq.fade,
+                len: seq.steps.len(),
+                cells: seq.steps
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(step, cells)| cells.iter().enumerate().filter_map(move |(fixture, cell)| {
+                        cell.map(|Cell { color, alpha }| SavedCell { step, fixture, color, alpha })
+                    }))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        sequences.sort_by_key(|seq| (seq.scene.1, seq.scene.0));
+        Self { sequences }
+    }
+
+    fn into_sequences(self) -> HashMap<(u8, u8), Sequence> {
+        self.sequences
+            .into_iter()
+            .map(|saved| {
+                let mut seq = Sequence::new(saved.len);
+                seq.pd = saved.pd;
+                seq.swing = saved.swing;
+                seq.fade = saved.fade;
+                for SavedCell { step, fixture, color, alpha } in saved.cells {
+                    seq.steps[step][fixture] = Some(Cell { color, alpha });
+                }
+                (saved.scene, seq)
+            })
+            .collect()
+    }
+}
+
+pub struct Sequencer {
+    pub editing: bool,
+    pub scene: Option<(u8, u8)>,
+    pub sequences: HashMap<(u8, u8), Sequence>,
+    pub len: usize,
+    /// Where sequences are saved when editing ends.
+    pub path: Option<PathBuf>,
+
+    row: usize,
+    page: usize,
+
+    /// What each grid cell was last set to while editing, so unchanged cells aren't resent.
+    sent: Mutex<[[Option<PaletteColor>; 8]; 8]>,
+}
+
+impl Sequencer {
+    pub fn new() -> Self {
+        Self {
+            editing: false,
+            scene: None,
+            sequences: HashMap::new(),
+            len: 16,
+            path: None,
+
+            row: 0,
+            page: 0,
+
+            sent: Mutex::new([[None; 8]; 8]),
+        }
+    }
+
+    /// Sequences from `path`, saved back there after each edit. A missing file
+    /// starts empty.
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut sequencer = Self::new();
+        if path.exists() {
+            sequencer.sequences = SequenceFile::load(path)?.into_sequences();
+        }
+        sequencer.path = Some(path.into());
+        Ok(sequencer)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        SequenceFile::from_sequences(&self.sequences).save(path)
+    }
+
+    /// Forget what the grid shows, e.g. after the Launchpad reconnects.
+    pub fn resend(&self) {
+        *self.sent.lock() = [[None; 8]; 8];
+    }
+
+    pub fn reset(&mut self) {
+        self.editing = false;
+        self.row = 0;
+        self.page = 0;
+    }
+
+    pub fn select(&mut self, x: u8, y: u8) {
+        self.scene = Some((x, y));
+    }
+
+    pub fn sequence(&self) -> Option<&Sequence> {
+        self.scene.and_then(|scene| self.sequences.get(&scene))
+    }
+
+    fn sequence_mut(&mut self) -> Option<&mut Sequence> {
+        let (scene, len) = (self.scene?, self.len);
+        Some(self.sequences.entry(scene).or_insert_with(|| Sequence::new(len)))
+    }
+
+    fn cell(state: &State, cell: Cell) -> Color {
+        match cell.color {
+            StepColor::Off => Color::OFF,
+            StepColor::Color0 => state.color0().a_mul(cell.alpha),
+            StepColor::Color1 => state.color1().a_mul(cell.alpha),
+        }
+    }
+
+    fn apply(lights: &mut Lights, i: usize, color: Color) {
+        match i {
+            0..=9 => lights.pars[i].color = color,
+            10..=13 => lights.beams[i - 10].color = color,
+            14..=15 => lights.bars[i - 14].color = color,
+            16..=17 => {
+                lights.spiders[i - 16].color0 = color;
+                lights.spiders[i - 16].color1 = color;
+            },
+            18 => lights.strobe.color = color,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[async_trait]
+impl Logic for Sequencer {
+    fn action(&mut self, _: &mut State, action: Action) {
+        match action {
+            Action::Button(Button::Edit) => {
+                let done = self.editing;
+                self.editing = !self.editing && self.sequence_mut().is_some();
+                if let (true, Some(path)) = (done, &self.path) {
+                    if let Err(e) = self.save(path) {
+                        log::warn!("Failed to save sequences: {:?}", e);
+                    }
+                }
+            },
+            _ if !self.editing => {},
+
+            Action::Scroll(dx, dy) => {
+                self.page = (self.page as isize + dx as isize).clamp(0, ((self.len - 1) / 8) as isize) as usize;
+                self.row = (self.row as isize + dy as isize).clamp(0, (FIXTURES - 8) as isize) as usize;
+            },
+            Action::Grid(x, y, fr) => {
+                let (step, fixture) = (self.page * 8 + x as usize, self.row + 7 - y as usize);
+                let Some(seq) = self.sequence_mut() else { return };
+                let Some(step) = seq.steps.get_mut(step) else { return };
+
+                // Cycle pass-through -> color0 -> color1 -> off, with velocity as intensity.
+                step[fixture] = match step[fixture].map(|cell| cell.color) {
+                    None => Some(Cell { color: StepColor::Color0, alpha: fr }),
+                    Some(StepColor::Color0) => Some(Cell { color: StepColor::Color1, alpha: fr }),
+                    Some(StepColor::Color1) => Some(Cell { color: StepColor::Off, alpha: fr }),
+                    Some(StepColor::Off) => None,
+                };
+            },
+            Action::Fader(Fader::Swing, fr) => {
+                if let Some(seq) = self.sequence_mut() {
+                    seq.swing = fr * 0.5;
+                }
+            },
+            Action::Fader(Fader::Fade, fr) => {
+                if let Some(seq) = self.sequence_mut() {
+                    seq.fade = fr;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn render(&self, state: &State, lights: &mut Lights) {
+        let Some(seq) = self.sequence() else { return };
+        let (i, j, fade) = seq.position(state);
+
+        for fixture in 0..FIXTURES {
+            let (a, b) = (seq.steps[i][fixture], seq.steps[j][fixture]);
+            let color = match (a, b) {
+                (None, _) => continue,
+                (Some(a), Some(b)) if fade > 0.0 => Self::cell(state, a).mix(Self::cell(state, b), fade),
+                (Some(a), _) => Self::cell(state, a),
+            };
+            Self::apply(lights, fixture, color);
+        }
+    }
+
+    async fn feedback(&self, state: &State, pad: &Pad) {
+        if !self.editing {
+            self.resend();
+            return;
+        }
+        let Some(seq) = self.sequence() else { return };
+        let (i, _, _) = seq.position(state);
+
+        let mut updates = vec![];
+        {
+            let mut sent = self.sent.lock();
+            for y in 0..8 {
+                for x in 0..8 {
+                    let (step, fixture) = (self.page * 8 + x as usize, self.row + 7 - y as usize);
+                    let color = match seq.steps.get(step).and_then(|s| s[fixture]) {
+                        _ if step == i => PaletteColor::Yellow,
+                        None => PaletteColor::Off,
+                        Some(Cell { color: StepColor::Color0, .. }) => PaletteColor::White,
+                        Some(Cell { color: StepColor::Color1, .. }) => PaletteColor::Cyan,
+                        Some(Cell { color: StepColor::Off, .. }) => PaletteColor::Red,
+                    };
+
+                    let cell = &mut sent[y as usize][x as usize];
+                    if *cell != Some(color) {
+                        *cell = Some(color);
+                        updates.push((x, y, color));
+                    }
+                }
+            }
+        }
+
+        for (x, y, color) in updates {
+            pad.send(PadOutput::Light(Coord(x, y).into(), color)).await;
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use anyhow::{bail, Context as _};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use stagebridge::midi::device::launch_control_xl::types::{
+    Brightness as CtrlBrightness, Color as CtrlColor,
+};
+
+use crate::config;
+use crate::mapping::{Action, Button, Fader};
+use crate::State;
+
+use super::{CtrlLed, CtrlLeds, Logic};
+
+/// Visualizer stages on one page of the Focus and Control buttons.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StagePage {
+    #[serde(default)]
+    pub focus: Vec<String>,
+    #[serde(default)]
+    pub control: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Curve {
+    Linear,
+    /// `fr^n`, finer control at the bottom of the range for n > 1.
+    Pow(f32),
+    /// Smoothstep, finer control at both ends.
+    Smooth,
+}
+
+impl Curve {
+    pub fn apply(self, fr: f32) -> f32 {
+        match self {
+            Curve::Linear => fr,
+            Curve::Pow(n) => fr.powf(n),
+            Curve::Smooth => fr * fr * (3.0 - 2.0 * fr),
+        }
+    }
+}
+
+fn default_max() -> f32 { 1.0 }
+fn default_curve() -> Curve { Curve::Linear }
+
+/// A SendA knob sent to the visualizer as a parameter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageParam {
+    pub knob: u8,
+    /// OSC address, defaulting to `/param/<knob>`.
+    pub addr: Option<String>,
+    #[serde(default)]
+    pub min: f32,
+    #[serde(default = "default_max")]
+    pub max: f32,
+    #[serde(default = "default_curve")]
+    pub curve: Curve,
+}
+
+impl StageParam {
+    pub fn addr(&self) -> String {
+        self.addr.clone().unwrap_or_else(|| format!("/param/{}", self.knob))
+    }
+
+    pub fn value(&self, fr: f32) -> f32 {
+        self.min + (self.max - self.min) * self.curve.apply(fr.clamp(0.0, 1.0))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageConfig {
+    #[serde(rename = "page")]
+    pub pages: Vec<StagePage>,
+    #[serde(default, rename = "param")]
+    pub params: Vec<StageParam>,
+}
+
+impl Default for StageConfig {
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect();
+        Self {
+            pages: vec![StagePage {
+                focus: names(&["loading", "code", "tiles", "primes", "bubbles"]),
+                control: names(&["tri", "linewave", "torus", "wormhole", "stars"]),
+            }],
+            params: (0..8)
+                .map(|knob| StageParam { knob, addr: None, min: 0.0, max: 1.0, curve: Curve::Linear })
+                .collect(),
+        }
+    }
+}
+
+impl StageConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let config: Self = config::load_toml(path)?;
+        config.validate().with_context(|| format!("checking {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Every page must fit on the stage buttons, since the rest are toggles.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (i, page) in self.pages.iter().enumerate() {
+            for (row, names) in [("focus", &page.focus), ("control", &page.control)] {
+                if names.len() > STAGE_BUTTONS {
+                    bail!("page {}: {} {:?} past the {} stage buttons", i, row, &names[STAGE_BUTTONS..], STAGE_BUTTONS);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Focus and Control buttons per page used for stages. The rest are toggles.
+pub const STAGE_BUTTONS: usize = 5;
+
+/// Visualizer stage selection and parameter knobs on the Launch Control.
+///
+/// Focus and Control buttons 0 to 4 pick a stage from the current page, and
+/// Up/Down flip pages. Focus 5 toggles the laser, Control 5 cycles the clock
+/// source and Control 7 is blackout, in `mapping::launch_control`.
+pub struct Stages {
+    pub config: StageConfig,
+    pub page: usize,
+    /// The active stage, and a count of switches so outputs can spot a new one.
+    pub active: Option<String>,
+    pub switches: u64,
+    /// Current value of each configured param, by address.
+    pub params: Vec<(String, f32)>,
+}
+
+impl Stages {
+    pub fn new(config: StageConfig) -> Self {
+        Self {
+            config,
+            page: 0,
+            active: None,
+            switches: 0,
+            params: vec![],
+        }
+    }
+
+    fn switch(&mut self, name: Option<&String>) {
+        if let Some(name) = name {
+            self.active = Some(name.clone());
+            self.switches += 1;
+        }
+    }
+
+    fn led(&self, name: Option<&String>) -> Option<(CtrlColor, CtrlBrightness)> {
+        match name {
+            Some(name) if self.active.as_ref() == Some(name) => Some((CtrlColor::Green, CtrlBrightness::High)),
+            Some(_) => Some((CtrlColor::Green, CtrlBrightness::Low)),
+            None => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Logic for Stages {
+    fn action(&mut self, _: &mut State, action: Action) {
+        let pages = self.config.pages.len();
+        let page = self.config.pages.get(self.page).cloned().unwrap_or_default();
+        match action {
+            Action::Stage(0, i) => self.switch(page.focus.get(i as usize)),
+            Action::Stage(1, i) => self.switch(page.control.get(i as usize)),
+            Action::Button(Button::StageUp) if self.page > 0 => self.page -= 1,
+            Action::Button(Button::StageDown) if self.page + 1 < pages => self.page += 1,
+            Action::Fader(Fader::Param(i), fr) => {
+                for param in self.config.params.iter().filter(|p| p.knob == i) {
+                    let addr = param.addr();
+                    let value = param.value(fr);
+                    match self.params.iter_mut().find(|(a, _)| *a == addr) {
+                        Some((_, v)) => *v = value,
+                        None => self.params.push((addr, value)),
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn leds(&self, _: &State, leds: &mut CtrlLeds) {
+        if let Some(page) = self.config.pages.get(self.page) {
+            for i in 0..STAGE_BUTTONS {
+                leds.extend(self.led(page.focus.get(i)).map(|led| (CtrlLed::Focus(i as u8), led)));
+                leds.extend(self.led(page.control.get(i)).map(|led| (CtrlLed::Control(i as u8), led)));
+            }
+        }
+        if self.page > 0 {
+            leds.insert(CtrlLed::Up, (CtrlColor::Red, CtrlBrightness::High));
+        }
+        if self.page + 1 < self.config.pages.len() {
+            leds.insert(CtrlLed::Down, (CtrlColor::Red, CtrlBrightness::High));
+        }
+
+        // Knobs with a param assigned.
+        for param in &self.config.params {
+            leds.insert(CtrlLed::SendA(param.knob), (CtrlColor::Amber, CtrlBrightness::Low));
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Controller input as abstract actions.
+//!
+//! The Launchpad X and Launch Control XL map to actions in code as the default
+//! layout. Any other MIDI controller can be bound with a mapping file:
+//!
+//! ```toml
+//! port = "APC40"
+//!
+//! [[bind]]
+//! note = 53
+//! scene = "low-0"
+//!
+//! [[bind]]
+//! cc = 7
+//! channel = 0
+//! fader = "alpha"
+//!
+//! [[bind]]
+//! cc = 48
+//! fader = { group = "beams" }
+//!
+//! [[bind]]
+//! note = 82
+//! button = "blackout"
+//! ```
+//!
+//! Bindings can also be learned: arm a target with `/learn`, e.g.
+//! `/learn "group/beams"`, then move or press a control on the mapped
+//! controller. The binding replaces any other for that control and the file is
+//! rewritten.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{anyhow, bail, Context as _};
+use serde::{Deserialize, Serialize};
+
+use stagebridge::midi::device::launch_control_xl::Input as CtrlInput;
+use stagebridge::midi::device::launchpad_x::types::{Coord, Pos};
+use stagebridge::midi::device::launchpad_x::Input as PadInput;
+
+use crate::config;
+use crate::lights::Group;
+use crate::logic::STAGE_BUTTONS;
+use crate::show::{scene_pos, Show, SCENES};
+
+/// A continuous control, 0..1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fader {
+    Alpha,
+    Fr0,
+    Fr1,
+    Group(Group),
+    /// Swing of the sequence being edited.
+    Swing,
+    /// Step crossfade of the sequence being edited.
+    Fade,
+    /// A visualizer param knob.
+    Param(u8),
+}
+
+/// A momentary control.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Button {
+    Blackout,
+    Laser,
+    ClockSource,
+    /// Toggle sequencer editing.
+    Edit,
+    Go,
+    Back,
+    Pause,
+    StageUp,
+    StageDown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Press on the 8x8 grid with velocity: a scene, or a sequencer cell while editing.
+    Grid(u8, u8, f32),
+    /// Trigger a scene regardless of mode.
+    Scene(u8, u8),
+    Fader(Fader, f32),
+    Button(Button),
+    PhiMul(f32),
+    /// Visualizer stage button `i` in row 0 (Focus) or 1 (Control).
+    Stage(u8, u8),
+    /// Move the sequencer view by pages and rows.
+    Scroll(i8, i8),
+}
+
+/// Default Launchpad X layout.
+pub fn launchpad(input: PadInput) -> Option<Action> {
+    Some(match input {
+        PadInput::Press(pos, fr) => {
+            let Coord(x, y) = Pos::from(pos).into();
+            if x > 7 || y > 7 {
+                return None;
+            }
+            Action::Grid(x as u8, y as u8, fr)
+        },
+        PadInput::Capture(true) => Action::Button(Button::ClockSource),
+        PadInput::Custom(true) => Action::Button(Button::Edit),
+        PadInput::Up(true) => Action::Scroll(0, -1),
+        PadInput::Down(true) => Action::Scroll(0, 1),
+        PadInput::Left(true) => Action::Scroll(-1, 0),
+        PadInput::Right(true) => Action::Scroll(1, 0),
+        _ => return None,
+    })
+}
+
+/// Default Launch Control XL layout.
+pub fn launch_control(input: CtrlInput) -> Option<Action> {
+    Some(match input {
+        CtrlInput::Slider(0, fr) => Action::Fader(Fader::Alpha, fr),
+        CtrlInput::Slider(1, fr) => Action::Fader(Fader::Fr0, fr),
+        CtrlInput::Slider(2, fr) => Action::Fader(Fader::Fr1, fr),
+        CtrlInput::Slider(6, fr) => Action::Fader(Fader::Swing, fr),
+        CtrlInput::Slider(7, fr) => Action::Fader(Fader::Fade, fr),
+        CtrlInput::SendA(i, fr) => Action::Fader(Fader::Param(i), (fr + 1.0) / 2.0),
+
+        CtrlInput::Focus(i, true) if (i as usize) < STAGE_BUTTONS => Action::Stage(0, i),
+        CtrlInput::Control(i, true) if (i as usize) < STAGE_BUTTONS => Action::Stage(1, i),
+        CtrlInput::Focus(5, true) => Action::Button(Button::Laser),
+        CtrlInput::Control(5, true) => Action::Button(Button::ClockSource),
+        CtrlInput::Control(7, true) => Action::Button(Button::Blackout),
+
+        CtrlInput::Up(true) => Action::Button(Button::StageUp),
+        CtrlInput::Down(true) => Action::Button(Button::StageDown),
+        CtrlInput::Left(true) => Action::Button(Button::Back),
+        CtrlInput::Right(true) => Action::Button(Button::Go),
+        CtrlInput::Device(true) => Action::Button(Button::Pause),
+
+        CtrlInput::Mute(true) => Action::PhiMul(0.5),
+        CtrlInput::Solo(true) => Action::PhiMul(1.0),
+        CtrlInput::Record(true) => Action::PhiMul(2.0),
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SceneRef {
+    Name(String),
+    Pos(u8, u8),
+}
+
+/// What a bound note or CC does.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Scene(SceneRef),
+    Grid(u8, u8),
+    Fader(Fader),
+    Button(Button),
+    PhiMul(f32),
+    Stage(u8, u8),
+}
+
+/// A unit variant by its serde name.
+fn unit<T: for<'de> Deserialize<'de>>(name: &str) -> Option<T> {
+    toml::Value::String(name.into()).try_into().ok()
+}
+
+fn unit_name<T: Serialize>(value: &T) -> String {
+    match toml::Value::try_from(value) {
+        Ok(toml::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+impl Target {
+    /// Parse a learn target, e.g. `alpha`, `group/beams`, `param/3`,
+    /// `scene/low-0`, `go`, `phi_mul/2` or `stage/1/4`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let num = |s: &str| s.parse::<u8>().ok();
+        let target = match name.split('/').collect::<Vec<_>>()[..] {
+            ["group", group] => Target::Fader(Fader::Group(Group::parse(group)?)),
+            ["param", i] => Target::Fader(Fader::Param(num(i)?)),
+            ["scene", scene] => {
+                scene_pos(scene)?;
+                Target::Scene(SceneRef::Name(scene.into()))
+            },
+            ["grid", x, y] => Target::Grid(num(x)?, num(y)?),
+            ["phi_mul", mul] => Target::PhiMul(mul.parse().ok()?),
+            ["stage", row, i] => Target::Stage(num(row)?, num(i)?),
+            [name] => match unit::<Fader>(name) {
+                Some(fader) => Target::Fader(fader),
+                None => Target::Button(unit(name)?),
+            },
+            _ => return None,
+        };
+        target.validate().is_ok().then_some(target)
+    }
+
+    /// The name accepted by `parse`, if the target is valid.
+    pub fn name(&self) -> Option<String> {
+        self.validate().ok()?;
+        Some(match self {
+            Target::Fader(Fader::Group(group)) => format!("group/{}", group.name()),
+            Target::Fader(Fader::Param(i)) => format!("param/{}", i),
+            Target::Fader(fader) => unit_name(fader),
+            Target::Button(button) => unit_name(button),
+            Target::Scene(SceneRef::Name(name)) => format!("scene/{}", name),
+            Target::Scene(SceneRef::Pos(x, y)) => match SCENES[*y as usize][*x as usize] {
+                "" => return None,
+                name => format!("scene/{}", name),
+            },
+            Target::Grid(x, y) => format!("grid/{}/{}", x, y),
+            Target::PhiMul(mul) => format!("phi_mul/{}", mul),
+            Target::Stage(row, i) => format!("stage/{}/{}", row, i),
+        })
+    }
+
+    /// The `key = value` this target is written as in a mapping file. Built by
+    /// hand since toml can't serialize enums with data.
+    fn to_toml(&self) -> (&'static str, toml::Value) {
+        use toml::Value;
+        let pair = |a: u8, b: u8| Value::Array(vec![Value::Integer(a as i64), Value::Integer(b as i64)]);
+        let table = |key: &str, value: Value| Value::Table([(key.to_string(), value)].into_iter().collect());
+        match self {
+            Target::Scene(SceneRef::Name(name)) => ("scene", Value::String(name.clone())),
+            Target::Scene(SceneRef::Pos(x, y)) => ("scene", pair(*x, *y)),
+            Target::Grid(x, y) => ("grid", pair(*x, *y)),
+            Target::Fader(Fader::Group(group)) => ("fader", table("group", Value::String(group.name().into()))),
+            Target::Fader(Fader::Param(i)) => ("fader", table("param", Value::Integer(*i as i64))),
+            Target::Fader(fader) => ("fader", Value::String(unit_name(fader))),
+            Target::Button(button) => ("button", Value::String(unit_name(button))),
+            Target::PhiMul(mul) => ("phi_mul", Value::Float(*mul as f64)),
+            Target::Stage(row, i) => ("stage", pair(*row, *i)),
+        }
+    }
+
+    /// Grid positions and stage rows must be on the controls they name.
+    fn validate(&self) -> anyhow::Result<()> {
+        match *self {
+            Target::Scene(SceneRef::Pos(x, y)) | Target::Grid(x, y) if x > 7 || y > 7 => {
+                bail!("({}, {}) is off the 8x8 grid", x, y)
+            },
+            Target::Stage(row, _) if row > 1 => bail!("stage row {} must be 0 or 1", row),
+            _ => Ok(()),
+        }
+    }
+
+    /// The action for `msg`. Everything but faders only fires on press: any
+    /// note on, or a CC past half way.
+    fn action(&self, msg: &MidiMsg) -> Option<Action> {
+        let fr = msg.fr;
+        let pressed = match msg.note {
+            Some(_) => fr > 0.0,
+            None => fr >= 0.5,
+        };
+        match self {
+            Target::Fader(fader) => Some(Action::Fader(*fader, fr)),
+            _ if !pressed => None,
+            Target::Scene(SceneRef::Name(name)) => scene_pos(name).map(|(x, y)| Action::Scene(x, y)),
+            Target::Scene(SceneRef::Pos(x, y)) => Some(Action::Scene(*x, *y)),
+            Target::Grid(x, y) => Some(Action::Grid(*x, *y, fr)),
+            Target::Button(button) => Some(Action::Button(*button)),
+            Target::PhiMul(mul) => Some(Action::PhiMul(*mul)),
+            Target::Stage(row, i) => Some(Action::Stage(*row, *i)),
+        }
+    }
+}
+
+/// A note or CC message, with the value as 0..1.
+#[derive(Clone, Copy, Debug)]
+pub struct MidiMsg {
+    pub channel: u8,
+    pub note: Option<u8>,
+    pub cc: Option<u8>,
+    pub fr: f32,
+}
+
+impl MidiMsg {
+    pub fn parse(msg: &[u8]) -> Option<Self> {
+        let (channel, status) = (msg.first()? & 0x0F, msg.first()? & 0xF0);
+        Some(match *msg {
+            [_, note, vel] if status == 0x90 => Self { channel, note: Some(note), cc: None, fr: vel as f32 / 127.0 },
+            [_, note, _] if status == 0x80 => Self { channel, note: Some(note), cc: None, fr: 0.0 },
+            [_, cc, value] if status == 0xB0 => Self { channel, note: None, cc: Some(cc), fr: value as f32 / 127.0 },
+            _ => return None,
+        })
+    }
+
+    /// Note off, or note on with zero velocity.
+    fn release(&self) -> bool {
+        self.note.is_some() && self.fr == 0.0
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    /// MIDI channel 0-15, or any.
+    pub channel: Option<u8>,
+    pub note: Option<u8>,
+    pub cc: Option<u8>,
+    #[serde(flatten)]
+    pub target: Target,
+}
+
+impl Binding {
+    fn matches(&self, msg: &MidiMsg) -> bool {
+        self.channel.map_or(true, |c| c == msg.channel)
+            && ((msg.note.is_some() && self.note == msg.note) || (msg.cc.is_some() && self.cc == msg.cc))
+    }
+
+    fn to_toml(&self) -> toml::Value {
+        let mut table = toml::value::Table::new();
+        for (key, value) in [("channel", self.channel), ("note", self.note), ("cc", self.cc)] {
+            if let Some(value) = value {
+                table.insert(key.into(), toml::Value::Integer(value as i64));
+            }
+        }
+        let (key, value) = self.target.to_toml();
+        table.insert(key.into(), value);
+        toml::Value::Table(table)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MidiMap {
+    /// Input port name, or part of it.
+    pub port: String,
+    #[serde(default, rename = "bind")]
+    pub binds: Vec<Binding>,
+}
+
+impl MidiMap {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let map: Self = config::load_toml(path)?;
+        for binding in &map.binds {
+            binding.target.validate().with_context(|| format!("checking {}", path.display()))?;
+        }
+        Ok(map)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let mut root = toml::value::Table::new();
+        root.insert("port".into(), toml::Value::String(self.port.clone()));
+        root.insert("bind".into(), toml::Value::Array(self.binds.iter().map(Binding::to_toml).collect()));
+        let text = toml::to_string(&toml::Value::Table(root))?;
+        std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn map(&self, msg: &MidiMsg) -> Vec<Action> {
+        self.binds
+            .iter()
+            .filter(|b| b.matches(msg))
+            .filter_map(|b| b.target.action(msg))
+            .collect()
+    }
+
+    /// Bind the control that sent `msg` to `target` alone.
+    pub fn learn(&mut self, msg: &MidiMsg, target: Target) {
+        let binding = Binding {
+            channel: Some(msg.channel),
+            note: msg.note,
+            cc: msg.cc,
+            target,
+        };
+        self.binds.retain(|b| !b.matches(msg));
+        self.binds.push(binding);
+    }
+}
+
+/// A mapped controller and the file its bindings are kept in.
+pub struct Mapper {
+    pub map: MidiMap,
+    pub path: PathBuf,
+    rx: mpsc::Receiver<MidiMsg>,
+}
+
+impl Mapper {
+    /// `path`, or `port=path` to start a new mapping file for `port` if there
+    /// isn't one yet.
+    pub fn open(spec: &str) -> anyhow::Result<Self> {
+        let (port, path) = match spec.split_once('=') {
+            Some((port, path)) => (Some(port), path),
+            None => (None, spec),
+        };
+        let map = match port {
+            Some(port) if !Path::new(path).exists() => MidiMap { port: port.into(), binds: vec![] },
+            _ => MidiMap::load(path)?,
+        };
+        Ok(Self {
+            rx: spawn(map.port.clone()),
+            map,
+            path: path.into(),
+        })
+    }
+
+    /// Apply input since the last frame to the show, or learn from it if a
+    /// target is armed.
+    pub fn poll(&mut self, show: &mut Show) {
+        while let Ok(msg) = self.rx.try_recv() {
+            match show.learn.take() {
+                Some(target) if !msg.release() => {
+                    log::info!("Learned {:?} for {:?} on {}", msg, target, self.map.port);
+                    self.map.learn(&msg, target);
+                    if let Err(e) = self.map.save(&self.path) {
+                        log::warn!("Failed to save MIDI mapping: {:?}", e);
+                    }
+                    continue;
+                },
+                learn => show.learn = learn,
+            }
+            for action in self.map.map(&msg) {
+                log::trace!("Mapped: {:?}", action);
+                show.action(action);
+            }
+        }
+    }
+}
+
+/// Listen to `port` on a background thread.
+fn spawn(port: String) -> mpsc::Receiver<MidiMsg> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if let Err(e) = run(&port, tx) {
+            log::warn!("MIDI mapping for {:?} stopped: {:?}", port, e);
+        }
+    });
+    rx
+}
+
+fn run(port: &str, tx: mpsc::Sender<MidiMsg>) -> anyhow::Result<()> {
+    let midi = midir::MidiInput::new("milstrikelive-map")?;
+    let ports = midi.ports();
+    let found = ports
+        .iter()
+        .find(|p| midi.port_name(p).map(|name| name.contains(port)).unwrap_or(false))
+        .ok_or_else(|| anyhow!("no MIDI input matching {:?}", port))?;
+    log::info!("Mapping MIDI from {}", midi.port_name(found)?);
+
+    let _conn = midi
+        .connect(found, "map", move |_, msg, _| {
+            if let Some(msg) = MidiMsg::parse(msg) {
+                let _ = tx.send(msg);
+            }
+        }, ())
+        .map_err(|e| anyhow!("connecting to {:?}: {}", port, e))?;
+
+    loop {
+        thread::park();
+    }
+}
@@ -0,0 +1,103 @@
+use std::f32::consts::TAU;
+
+use stagebridge::num::Float;
+
+use crate::{fx, State, Pd};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Wave {
+    Sin,
+    Tri,
+    Square { duty: f32 },
+    Saw,
+    Random { seed: u64 },
+}
+
+/// How the phase of a motion is offset across a group of fixtures.
+#[derive(Clone, Copy, Debug)]
+pub enum Spread {
+    /// Every fixture in phase.
+    Same,
+    /// Offset by `fr` per fixture, left to right.
+    Fan(f32),
+    /// Offset by `fr` per fixture, outwards from the center.
+    Mirror(f32),
+    /// Offset by `fr` per pair of fixtures.
+    Pairs(f32),
+}
+
+/// A parameterized movement: `center + amp * wave`, with `wave` in -1..1.
+#[derive(Clone, Copy, Debug)]
+pub struct Motion {
+    pub wave: Wave,
+    pub pd: Pd,
+    pub center: f32,
+    pub amp: f32,
+    pub phase: f32,
+    pub spread: Spread,
+    /// Static per-fixture offsets added to `center`, repeated across the group.
+    pub layout: &'static [f32],
+}
+
+impl Motion {
+    pub const fn new(wave: Wave, pd: Pd, center: f32, amp: f32) -> Self {
+        Self {
+            wave,
+            pd,
+            center,
+            amp,
+            phase: 0.0,
+            spread: Spread::Same,
+            layout: &[],
+        }
+    }
+
+    pub const fn fixed(center: f32) -> Self {
+        Self::new(Wave::Sin, Pd(1, 1), center, 0.0)
+    }
+
+    pub const fn phase(self, phase: f32) -> Self {
+        Self { phase, ..self }
+    }
+
+    pub const fn spread(self, spread: Spread) -> Self {
+        Self { spread, ..self }
+    }
+
+    pub const fn layout(self, layout: &'static [f32]) -> Self {
+        Self { layout, ..self }
+    }
+
+    /// Position of fixture `i` out of `n` at the current time.
+    pub fn at(&self, state: &State, i: usize, n: usize) -> f32 {
+        let offset = self.phase + match self.spread {
+            Spread::Same => 0.0,
+            Spread::Fan(fr) => fr * i as f32,
+            Spread::Mirror(fr) => fr * (2.0 * i as f32 - (n as f32 - 1.0)).abs() / 2.0,
+            Spread::Pairs(fr) => fr * (i / 2) as f32,
+        };
+
+        let center = match self.layout.len() {
+            0 => self.center,
+            len => self.center + self.layout[i % len],
+        };
+
+        if self.amp == 0.0 {
+            return center;
+        }
+
+        let t = state.phi(self.pd).phase(1.0, offset.fract());
+        let v = match self.wave {
+            Wave::Sin => (t * TAU).sin(),
+            Wave::Tri => 2.0 * t.tri(1.0) - 1.0,
+            Wave::Square { duty } => t.negsquare(1.0, duty),
+            Wave::Saw => 2.0 * t - 1.0,
+            Wave::Random { seed } => {
+                let cycle = (state.phi / (self.pd.fr() * state.phi_mul) + offset).floor() as u64;
+                2.0 * fx::random(seed ^ i as u64, cycle) - 1.0
+            },
+        };
+
+        center + self.amp * v
+    }
+}
@@ -0,0 +1,216 @@
+//! OSC control API, received on port 7777 alongside the `/vdj/*` clock.
+//!
+//! | Address                          | Arguments                     |                                          |
+//! |----------------------------------|-------------------------------|------------------------------------------|
+//! | `/scene`                         | `x y` or `name`               | Trigger a scene, named as in `SCENES`    |
+//! | `/palette`                       | `name`                        | Trigger a color scene from rows 1 and 2  |
+//! | `/master/alpha`                  | `fr`                          | Grand master                             |
+//! | `/master/fr0`, `/master/fr1`     | `fr`                          |                                          |
+//! | `/master/phi_mul`                | `mul`                         | Clock multiplier, e.g. 0.5 for half time |
+//! | `/master/blackout`               | `0` or `1`                    |                                          |
+//! | `/group/<group>`                 | `fr`                          | Submaster for pars, beams, bars, strobe or spiders |
+//! | `/color/0`, `/color/1`           | `r g b [w]` or `name`         | Base colors, names as in `Color::named`  |
+//! | `/color/0/rainbow`, `/color/1/rainbow` | `beats`                 | Rainbow cycling over `beats`             |
+//! | `/clock/source`                  | `osc`, `static`, `audio` or `timecode` |                                 |
+//! | `/clock/bpm`                     | `bpm`                         | Tempo for the static and timecode clocks |
+//! | `/learn`                         | `target` or `off`             | Arm MIDI learn, targets as in `mapping::Target::parse` |
+//! | `/reply`                         | `host:port`                   | Where answers to queries are sent        |
+//! | `/stats`                         |                               | Query only: `fps frame_ms frame_max_ms jitter_ms jitter_max_ms missed dmx_hz` |
+//!
+//! Sending a value address with no arguments queries it, and the answer comes
+//! back to the `/reply` target with the same address. `/scene` answers with the
+//! last triggered grid position.
+
+use stagebridge::osc::{Message as OscMessage, Value as OscValue};
+
+use crate::*;
+
+pub struct Remote {
+    /// Where answers to queries are sent, set by `/reply` or `MSL_OSC_REPLY`.
+    pub reply: Option<String>,
+    /// Frame timing from the last report period, answered on `/stats`.
+    pub stats: FrameStats,
+}
+
+fn float(v: &OscValue) -> Option<f32> {
+    match *v {
+        OscValue::Float(f) => Some(f),
+        OscValue::Int(i) => Some(i as f32),
+        _ => None,
+    }
+}
+
+fn string(v: &OscValue) -> Option<&str> {
+    match v {
+        OscValue::String(s) => Some(s.as_str()),
+        _ => None,
+    }
+}
+
+fn reply(addr: &str, args: Vec<OscValue>) -> Vec<OscMessage> {
+    vec![OscMessage { addr: addr.into(), args }]
+}
+
+fn floats(values: &[f32]) -> Vec<OscValue> {
+    values.iter().map(|&f| OscValue::Float(f)).collect()
+}
+
+impl Remote {
+    pub fn new() -> Self {
+        Self {
+            reply: std::env::var("MSL_OSC_REPLY").ok(),
+            stats: FrameStats::default(),
+        }
+    }
+
+    /// Apply `msg` to the show, returning the answers to any queries.
+    pub fn handle(&mut self, show: &mut Show, msg: &OscMessage) -> Vec<OscMessage> {
+        let addr = msg.addr.as_str();
+        let args = &msg.args;
+        let query = args.is_empty();
+        let arg = |i: usize| args.get(i).and_then(float);
+
+        let state = &mut show.state;
+        match addr.split('/').skip(1).collect::<Vec<_>>()[..] {
+            ["reply"] => match args.first().and_then(string) {
+                Some(to) => self.reply = Some(to.into()),
+                None => log::warn!("OSC: /reply needs host:port"),
+            },
+
+            ["stats"] => return reply(addr, floats(&self.stats.values())),
+
+            ["learn"] if query => {
+                let name = show.learn.as_ref().and_then(|t| t.name()).unwrap_or_default();
+                return reply(addr, vec![OscValue::String(name)]);
+            },
+            ["learn"] => match args.first().and_then(string) {
+                Some("off" | "") => show.learn = None,
+                Some(name) => match mapping::Target::parse(name) {
+                    Some(target) => {
+                        log::info!("Learning {}, move a control", name);
+                        show.learn = Some(target);
+                    },
+                    None => log::warn!("OSC: unknown learn target {}", name),
+                },
+                None => log::warn!("OSC: /learn needs a target"),
+            },
+
+            ["scene"] if query => {
+                if let Some((x, y)) = show.current {
+                    return reply(addr, vec![OscValue::Int(x as i32), OscValue::Int(y as i32)]);
+                }
+            },
+            ["scene"] => {
+                let pos = match (args.first().and_then(string), arg(0), arg(1)) {
+                    (Some(name), _, _) => scene_pos(name),
+                    (None, Some(x), Some(y)) if (0.0..8.0).contains(&x) && (0.0..8.0).contains(&y) => {
+                        Some((x as u8, y as u8))
+                    },
+                    _ => None,
+                };
+                match pos {
+                    Some((x, y)) => show.scene(x, y),
+                    None => log::warn!("OSC: unknown scene {:?}", args),
+                }
+            },
+            ["palette"] => match args.first().and_then(string).and_then(scene_pos) {
+                Some((x, y)) if (1..=2).contains(&y) => show.scene(x, y),
+                _ => log::warn!("OSC: unknown palette {:?}", args),
+            },
+
+            ["master", name] => {
+                let value = match name {
+                    "alpha" => &mut state.alpha,
+                    "fr0" => &mut state.fr0,
+                    "fr1" => &mut state.fr1,
+                    "phi_mul" => &mut state.phi_mul,
+                    "blackout" if query => return reply(addr, vec![OscValue::Int(state.off as i32)]),
+                    "blackout" => {
+                        state.off = arg(0).map(|f| f > 0.5).unwrap_or(state.off);
+                        return vec![];
+                    },
+                    _ => {
+                        log::warn!("OSC: unknown master {}", addr);
+                        return vec![];
+                    },
+                };
+                match arg(0) {
+                    _ if query => return reply(addr, floats(&[*value])),
+                    Some(fr) => *value = fr,
+                    None => log::warn!("OSC: {} needs a number", addr),
+                }
+            },
+
+            ["group", name] => match Group::parse(name) {
+                Some(group) if query => return reply(addr, floats(&[state.group_alpha[group as usize]])),
+                Some(group) => {
+                    if let Some(fr) = arg(0) {
+                        state.group_alpha[group as usize] = fr;
+                    }
+                },
+                None => log::warn!("OSC: unknown group {}", name),
+            },
+
+            ["color", i @ ("0" | "1"), ..] => {
+                if query {
+                    let c = match i {
+                        "0" => state.color0.apply(&*state),
+                        _ => state.color1.apply(&*state),
+                    };
+                    return reply(addr, floats(&[c.r, c.g, c.b, c.w]));
+                }
+                let op = match i {
+                    "0" => &mut state.color0,
+                    _ => &mut state.color1,
+                };
+                let color = match (addr.ends_with("/rainbow"), args.first().and_then(string)) {
+                    (true, _) => arg(0).map(|beats| fx::rainbow(Pd(beats.max(1.0) as usize, 1))),
+                    (false, Some(name)) => Color::named(name).map(ColorOp::from),
+                    (false, None) => match (arg(0), arg(1), arg(2)) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::rgbw(r, g, b, arg(3).unwrap_or(0.0)).into()),
+                        _ => None,
+                    },
+                };
+                match color {
+                    Some(color) => *op = color,
+                    None => log::warn!("OSC: bad color {} {:?}", addr, args),
+                }
+            },
+
+            ["clock", "source"] if query => {
+                let name = match show.time.source {
+                    ClockSource::Osc => "osc",
+                    ClockSource::Static { .. } => "static",
+                    ClockSource::Audio => "audio",
+                    ClockSource::Timecode { .. } => "timecode",
+                };
+                return reply(addr, vec![OscValue::String(name.into())]);
+            },
+            ["clock", "source"] => {
+                let bpm = state.bpm;
+                let source = match args.first().and_then(string) {
+                    Some("osc") => Some(ClockSource::Osc),
+                    Some("static") => Some(ClockSource::Static { bpm }),
+                    Some("audio") if show.time.audio => Some(ClockSource::Audio),
+                    Some("timecode") if show.time.timecode => Some(ClockSource::Timecode { bpm }),
+                    _ => None,
+                };
+                match source {
+                    Some(source) => show.time.source = source,
+                    None => log::warn!("OSC: unavailable clock source {:?}", args),
+                }
+            },
+            ["clock", "bpm"] if query => return reply(addr, floats(&[state.bpm])),
+            ["clock", "bpm"] => match (&mut show.time.source, arg(0)) {
+                (ClockSource::Static { bpm } | ClockSource::Timecode { bpm }, Some(new)) => {
+                    *bpm = new;
+                    state.bpm = new;
+                },
+                _ => log::warn!("OSC: /clock/bpm only applies to the static and timecode clocks"),
+            },
+
+            _ => log::trace!("OSC: unhandled {}", addr),
+        }
+        vec![]
+    }
+}
@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::{anyhow, bail, Context as _};
+
+use stagebridge::dmx::DMX;
+
+use crate::{Clock, Color, Lights, ReplayClock, Show, StepClock};
+
+/// Where the beats fall in time.
+#[derive(Clone, Debug)]
+pub enum Tempo {
+    Bpm(f32),
+    /// Timestamp in seconds of each beat, at least two.
+    Map(Vec<f32>),
+}
+
+impl Tempo {
+    /// A fixed tempo, which must be positive.
+    pub fn bpm(bpm: f32) -> anyhow::Result<Self> {
+        if !bpm.is_finite() || bpm <= 0.0 {
+            bail!("bpm {} must be a positive number", bpm);
+        }
+        Ok(Tempo::Bpm(bpm))
+    }
+
+    /// A beat map with one timestamp per line, strictly increasing.
+    pub fn parse_map(text: &str) -> anyhow::Result<Self> {
+        let beats = text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if beats.len() < 2 {
+            bail!("a beat map needs at least two beats");
+        }
+        if let Some(b) = beats.iter().find(|b| !b.is_finite()) {
+            bail!("beat at {}s is not a time", b);
+        }
+        if let Some(k) = beats.windows(2).position(|w| w[1] <= w[0]) {
+            bail!("beat {} at {}s is not after the beat before it at {}s", k + 2, beats[k + 1], beats[k]);
+        }
+        Ok(Tempo::Map(beats))
+    }
+
+    /// Beats elapsed and the current bpm at `t` seconds.
+    pub fn at(&self, t: f32) -> (f32, f32) {
+        match self {
+            Tempo::Bpm(bpm) => (t * bpm / 60.0, *bpm),
+            Tempo::Map(beats) => {
+                let k = beats.partition_point(|&b| b <= t).saturating_sub(1).min(beats.len() - 2);
+                let (a, b) = (beats[k], beats[k + 1]);
+                (k as f32 + (t - a) / (b - a), 60.0 / (b - a))
+            }
+        }
+    }
+}
+
+/// A scene triggered at a beat.
+#[derive(Clone, Copy, Debug)]
+pub struct SceneAt {
+    pub beat: f32,
+    pub x: u8,
+    pub y: u8,
+}
+
+pub struct Render {
+    pub tempo: Tempo,
+    pub scenes: Vec<SceneAt>,
+}
+
+impl Render {
+    /// Run the show on the frames of `clock`, calling `frame` with the time,
+    /// lights and DMX buffer of every frame.
+    pub async fn run<F>(&self, clock: &mut dyn Clock, mut frame: F) -> anyhow::Result<usize>
+    where
+        F: FnMut(f32, &Lights, &[u8]) -> anyhow::Result<()>,
+    {
+        let mut show = Show::new();
+        let mut dmx = DMX::new(205);
+
+        let mut scenes = self.scenes.clone();
+        scenes.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        let mut scenes = scenes.into_iter().peekable();
+
+        let mut frames = 0;
+        while clock.tick().await {
+            let t = clock.now();
+            let (beats, bpm) = self.tempo.at(t);
+
+            show.tick(t);
+            let state = &mut show.state;
+            state.t = t;
+            state.bpm = bpm;
+            state.phi = beats.rem_euclid(16.0);
+
+            while let Some(scene) = scenes.next_if(|s| s.beat <= beats) {
+                log::debug!("{:.3}s: scene ({}, {})", t, scene.x, scene.y);
+                show.scene(scene.x, scene.y);
+            }
+
+            let lights = show.render();
+            lights.write(&mut dmx);
+            frame(t, &lights, dmx.buffer())?;
+            frames += 1;
+        }
+        Ok(frames)
+    }
+}
+
+fn csv_row<W: Write>(w: &mut W, t: f32, fixture: &str, c: Color, pos: Option<(f32, f32)>) -> std::io::Result<()> {
+    let (x, y) = match pos {
+        Some((x, y)) => (x.to_string(), y.to_string()),
+        None => (String::new(), String::new()),
+    };
+    writeln!(w, "{:.4},{},{},{},{},{},{},{},{}", t, fixture, c.a, c.r, c.g, c.b, c.w, x, y)
+}
+
+/// Per-fixture values as CSV rows of `t,fixture,a,r,g,b,w,x,y`.
+pub fn write_csv<W: Write>(w: &mut W, t: f32, lights: &Lights) -> std::io::Result<()> {
+    for (i, par) in lights.pars.iter().enumerate() {
+        csv_row(w, t, &format!("par{}", i), par.color, None)?;
+    }
+    for (i, beam) in lights.beams.iter().enumerate() {
+        csv_row(w, t, &format!("beam{}", i), beam.color, Some((beam.yaw, beam.pitch)))?;
+    }
+    csv_row(w, t, "strobe", lights.strobe.color, None)?;
+    for (i, bar) in lights.bars.iter().enumerate() {
+        csv_row(w, t, &format!("bar{}", i), bar.color, None)?;
+    }
+    for (i, spider) in lights.spiders.iter().enumerate() {
+        csv_row(w, t, &format!("spider{}.0", i), spider.color0, Some((spider.pos0, 0.0)))?;
+        csv_row(w, t, &format!("spider{}.1", i), spider.color1, Some((spider.pos1, 0.0)))?;
+    }
+    Ok(())
+}
+
+/// One line per frame: the timestamp in seconds, then the DMX buffer in hex.
+pub fn write_dmx<W: Write>(w: &mut W, t: f32, buffer: &[u8]) -> std::io::Result<()> {
+    write!(w, "{:.4} ", t)?;
+    for byte in buffer {
+        write!(w, "{:02x}", byte)?;
+    }
+    writeln!(w)
+}
+
+const USAGE: &str = "usage: render (--bpm BPM | --beats FILE) (--duration SECS [--fps FPS] | --replay FILE) \
+    [--scene BEAT:X,Y]... [--dmx FILE] [--csv FILE]";
+
+/// `render` subcommand. `--replay` takes the frame times from the first
+/// column of a file, such as an earlier `--dmx` output.
+pub async fn main(args: &[String]) -> anyhow::Result<()> {
+    let mut tempo = None;
+    let mut duration = None;
+    let mut fps = 200.0;
+    let mut replay = None;
+    let mut scenes = vec![];
+    let mut dmx_out = None;
+    let mut csv_out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value\n{}", arg, USAGE));
+        match arg.as_str() {
+            "--bpm" => tempo = Some(Tempo::bpm(value()?.parse()?)?),
+            "--beats" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+                tempo = Some(Tempo::parse_map(&text).with_context(|| format!("beat map {}", path))?);
+            },
+            "--duration" => duration = Some(value()?.parse()?),
+            "--fps" => fps = value()?.parse()?,
+            "--replay" => replay = Some(ReplayClock::load(value()?)?),
+            "--scene" => {
+                let v = value()?;
+                let parse = || -> Option<SceneAt> {
+                    let (beat, pos) = v.split_once(':')?;
+                    let (x, y) = pos.split_once(',')?;
+                    let scene = SceneAt { beat: beat.parse().ok()?, x: x.parse().ok()?, y: y.parse().ok()? };
+                    (scene.x < 8 && scene.y < 8).then_some(scene)
+                };
+                scenes.push(parse().ok_or_else(|| anyhow!("bad --scene {}\n{}", v, USAGE))?);
+            },
+            "--dmx" => dmx_out = Some(value()?.clone()),
+            "--csv" => csv_out = Some(value()?.clone()),
+            _ => bail!("unknown argument {}\n{}", arg, USAGE),
+        }
+    }
+
+    let render = Render {
+        tempo: tempo.ok_or_else(|| anyhow!("missing --bpm or --beats\n{}", USAGE))?,
+        scenes,
+    };
+    let mut clock: Box<dyn Clock> = match (replay, duration) {
+        (Some(replay), _) => Box::new(replay),
+        (None, Some(duration)) => Box::new(StepClock::new(1.0 / fps, Some(duration))),
+        (None, None) => bail!("missing --duration or --replay\n{}", USAGE),
+    };
+
+    let create = |path: &Option<String>| -> anyhow::Result<Option<BufWriter<File>>> {
+        path.as_ref()
+            .map(|p| File::create(p).map(BufWriter::new).with_context(|| format!("creating {}", p)))
+            .transpose()
+    };
+    let mut dmx = create(&dmx_out)?;
+    let mut csv = create(&csv_out)?;
+    if let Some(csv) = csv.as_mut() {
+        writeln!(csv, "t,fixture,a,r,g,b,w,x,y")?;
+    }
+
+    let frames = render.run(clock.as_mut(), |t, lights, buffer| {
+        if let Some(dmx) = dmx.as_mut() {
+            write_dmx(dmx, t, buffer)?;
+        }
+        if let Some(csv) = csv.as_mut() {
+            write_csv(csv, t, lights)?;
+        }
+        Ok(())
+    }).await?;
+
+    log::info!("Rendered {} frames", frames);
+    Ok(())
+}
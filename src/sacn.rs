@@ -0,0 +1,265 @@
+//! sACN (E1.31) output with per-universe priority, multicast, synchronization
+//! and stream termination.
+//!
+//! Configured from the environment:
+//!
+//! | Variable             |                                                              |
+//! |----------------------|--------------------------------------------------------------|
+//! | `MSL_SACN`           | Receiver address, or `multicast` for the standard universe groups. Default `10.16.4.1` |
+//! | `MSL_SACN_UNIVERSE`  | First universe, 1-63999, further 512 channel blocks go to the next ones. Default 1 |
+//! | `MSL_SACN_PRIORITY`  | `priority` for every universe or `universe:priority,...`, 0-200. Default 100 |
+//! | `MSL_SACN_SYNC`      | Synchronization universe, sent after every frame. Off by default |
+//! | `MSL_SACN_KEEPALIVE` | Seconds between resends of unchanged data. Default 1         |
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context as _};
+
+pub const SACN_PORT: u16 = 5568;
+pub const DEFAULT_PRIORITY: u8 = 100;
+const SLOTS: usize = 512;
+const SOURCE_NAME: &str = "milstrikelive";
+/// Universes 0 and 64000 and up are reserved.
+const UNIVERSES: std::ops::RangeInclusive<u16> = 1..=63999;
+
+const ACN_ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_DATA: u32 = 0x04;
+const VECTOR_ROOT_EXTENDED: u32 = 0x08;
+const VECTOR_FRAMING_DATA: u32 = 0x02;
+const VECTOR_FRAMING_SYNC: u32 = 0x01;
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+const OPTION_TERMINATED: u8 = 0x40;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Unicast(IpAddr),
+    /// 239.255.<universe hi>.<universe lo>.
+    Multicast,
+}
+
+impl Target {
+    fn addr(&self, universe: u16) -> SocketAddr {
+        let ip = match *self {
+            Target::Unicast(ip) => ip,
+            Target::Multicast => Ipv4Addr::new(239, 255, (universe >> 8) as u8, universe as u8).into(),
+        };
+        SocketAddr::new(ip, SACN_PORT)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SacnConfig {
+    pub target: Target,
+    pub universe: u16,
+    pub priority: u8,
+    /// Priority overrides by universe.
+    pub priorities: BTreeMap<u16, u8>,
+    pub sync: Option<u16>,
+    pub keepalive: Duration,
+}
+
+impl Default for SacnConfig {
+    fn default() -> Self {
+        Self {
+            target: Target::Unicast(Ipv4Addr::new(10, 16, 4, 1).into()),
+            universe: 1,
+            priority: DEFAULT_PRIORITY,
+            priorities: BTreeMap::new(),
+            sync: None,
+            keepalive: Duration::from_secs(1),
+        }
+    }
+}
+
+impl SacnConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        let var = |name: &str| std::env::var(name).ok().filter(|s| !s.is_empty());
+        let universe = |name: &str, s: &str| match s.parse::<u16>() {
+            Ok(u) if UNIVERSES.contains(&u) => Ok(u),
+            _ => Err(anyhow!("{} {:?} is not a universe 1-63999", name, s)),
+        };
+
+        if let Some(target) = var("MSL_SACN") {
+            config.target = match target.as_str() {
+                "multicast" => Target::Multicast,
+                ip => Target::Unicast(ip.parse().with_context(|| format!("MSL_SACN {:?}", ip))?),
+            };
+        }
+        if let Some(s) = var("MSL_SACN_UNIVERSE") {
+            config.universe = universe("MSL_SACN_UNIVERSE", &s)?;
+        }
+        if let Some(priorities) = var("MSL_SACN_PRIORITY") {
+            let priority = |s: &str| match s.parse::<u8>() {
+                Ok(p) if p <= 200 => Ok(p),
+                _ => Err(anyhow!("MSL_SACN_PRIORITY {:?} is not 0-200", s)),
+            };
+            for entry in priorities.split(',') {
+                match entry.split_once(':') {
+                    Some((u, p)) => {
+                        config.priorities.insert(universe("MSL_SACN_PRIORITY", u)?, priority(p)?);
+                    },
+                    None => config.priority = priority(entry)?,
+                }
+            }
+        }
+        if let Some(s) = var("MSL_SACN_SYNC") {
+            config.sync = Some(universe("MSL_SACN_SYNC", &s)?);
+        }
+        if let Some(s) = var("MSL_SACN_KEEPALIVE") {
+            config.keepalive = s.parse::<f32>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f32(secs).ok())
+                .filter(|d| !d.is_zero())
+                .ok_or_else(|| anyhow!("MSL_SACN_KEEPALIVE {:?} is not a positive number of seconds", s))?;
+        }
+        Ok(config)
+    }
+
+    pub fn priority(&self, universe: u16) -> u8 {
+        self.priorities.get(&universe).copied().unwrap_or(self.priority)
+    }
+}
+
+fn flags_length(buf: &mut [u8], at: usize) {
+    let len = (buf.len() - at) as u16;
+    buf[at..at + 2].copy_from_slice(&(0x7000 | len).to_be_bytes());
+}
+
+/// The root layer shared by every packet, with lengths filled in by the caller.
+fn root(len: usize, vector: u32, cid: &[u8; 16]) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    buf[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+    buf[4..16].copy_from_slice(ACN_ID);
+    buf[18..22].copy_from_slice(&vector.to_be_bytes());
+    buf[22..38].copy_from_slice(cid);
+    buf
+}
+
+/// An E1.31 data packet for one universe.
+pub fn data_packet(cid: &[u8; 16], universe: u16, priority: u8, sync: u16, seq: u8, options: u8, data: &[u8]) -> Vec<u8> {
+    let data = &data[..data.len().min(SLOTS)];
+    let mut buf = root(126 + data.len(), VECTOR_ROOT_DATA, cid);
+
+    buf[40..44].copy_from_slice(&VECTOR_FRAMING_DATA.to_be_bytes());
+    let name = SOURCE_NAME.as_bytes();
+    buf[44..44 + name.len()].copy_from_slice(name);
+    buf[108] = priority;
+    buf[109..111].copy_from_slice(&sync.to_be_bytes());
+    buf[111] = seq;
+    buf[112] = options;
+    buf[113..115].copy_from_slice(&universe.to_be_bytes());
+
+    buf[117] = VECTOR_DMP_SET_PROPERTY;
+    buf[118] = 0xa1;
+    buf[121..123].copy_from_slice(&1u16.to_be_bytes());
+    buf[123..125].copy_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+    // buf[125]: start code 0
+    buf[126..].copy_from_slice(data);
+
+    flags_length(&mut buf, 16);
+    flags_length(&mut buf, 38);
+    flags_length(&mut buf, 115);
+    buf
+}
+
+/// An E1.31 synchronization packet.
+pub fn sync_packet(cid: &[u8; 16], sync: u16, seq: u8) -> Vec<u8> {
+    let mut buf = root(49, VECTOR_ROOT_EXTENDED, cid);
+    buf[40..44].copy_from_slice(&VECTOR_FRAMING_SYNC.to_be_bytes());
+    buf[44] = seq;
+    buf[45..47].copy_from_slice(&sync.to_be_bytes());
+    flags_length(&mut buf, 16);
+    flags_length(&mut buf, 38);
+    buf
+}
+
+/// Sends a DMX buffer as consecutive universes. Streams are terminated when
+/// this is dropped, so receivers release them straight away instead of
+/// waiting for a timeout.
+pub struct Sacn {
+    pub config: SacnConfig,
+    socket: UdpSocket,
+    cid: [u8; 16],
+    seq: BTreeMap<u16, u8>,
+    sync_seq: u8,
+    /// The last data sent, for termination.
+    last: Vec<u8>,
+}
+
+impl Sacn {
+    pub fn new(config: SacnConfig) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").context("binding sACN socket")?;
+        if config.target == Target::Multicast {
+            socket.set_multicast_ttl_v4(8)?;
+        }
+        Ok(Self {
+            config,
+            socket,
+            cid: rand::random(),
+            seq: BTreeMap::new(),
+            sync_seq: 0,
+            last: vec![],
+        })
+    }
+
+    fn universes<'a>(&self, buffer: &'a [u8]) -> impl Iterator<Item = (u16, &'a [u8])> {
+        let first = self.config.universe;
+        buffer.chunks(SLOTS).enumerate().map(move |(i, data)| (first + i as u16, data))
+    }
+
+    fn send_universes(&mut self, buffer: &[u8], options: u8) {
+        let universes = self.universes(buffer).collect::<Vec<_>>();
+        for (universe, data) in universes {
+            let seq = self.seq.entry(universe).or_insert(0);
+            *seq = seq.wrapping_add(1);
+            let packet = data_packet(
+                &self.cid,
+                universe,
+                self.config.priority(universe),
+                self.config.sync.unwrap_or(0),
+                *seq,
+                options,
+                data,
+            );
+            if let Err(e) = self.socket.send_to(&packet, self.config.target.addr(universe)) {
+                log::debug!("sACN send to universe {} failed: {:?}", universe, e);
+            }
+        }
+    }
+
+    /// Send a frame, followed by a sync packet if configured.
+    pub fn send(&mut self, buffer: &[u8]) {
+        self.send_universes(buffer, 0);
+        if let Some(sync) = self.config.sync {
+            self.sync_seq = self.sync_seq.wrapping_add(1);
+            let packet = sync_packet(&self.cid, sync, self.sync_seq);
+            if let Err(e) = self.socket.send_to(&packet, self.config.target.addr(sync)) {
+                log::debug!("sACN sync failed: {:?}", e);
+            }
+        }
+        self.last.clear();
+        self.last.extend_from_slice(buffer);
+    }
+
+    /// Mark every universe as terminated, three times as E1.31 asks.
+    pub fn terminate(&mut self) {
+        if self.last.is_empty() {
+            return;
+        }
+        let last = std::mem::take(&mut self.last);
+        for _ in 0..3 {
+            self.send_universes(&last, OPTION_TERMINATED);
+        }
+        log::info!("sACN streams terminated");
+    }
+}
+
+impl Drop for Sacn {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
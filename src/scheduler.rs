@@ -0,0 +1,189 @@
+//! Frame pacing for the main loop: renders at one rate, sends DMX at another,
+//! and keeps timing statistics.
+
+use std::time::{Duration, Instant};
+
+/// Running statistics of a duration in seconds since the last report.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    pub count: u64,
+    sum: f64,
+    sum_sq: f64,
+    pub max: f32,
+}
+
+impl Stats {
+    pub fn record(&mut self, secs: f32) {
+        self.count += 1;
+        self.sum += secs as f64;
+        self.sum_sq += (secs as f64) * (secs as f64);
+        self.max = self.max.max(secs);
+    }
+
+    pub fn mean(&self) -> f32 {
+        match self.count {
+            0 => 0.0,
+            n => (self.sum / n as f64) as f32,
+        }
+    }
+
+    pub fn std_dev(&self) -> f32 {
+        match self.count {
+            0 => 0.0,
+            n => {
+                let mean = self.sum / n as f64;
+                (self.sum_sq / n as f64 - mean * mean).max(0.0).sqrt() as f32
+            },
+        }
+    }
+}
+
+/// Timing over one report period.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    /// Rendered and sent frames per second.
+    pub fps: f32,
+    pub output_hz: f32,
+    /// Time spent in the loop body per frame.
+    pub frame: Stats,
+    /// How far each tick landed from its schedule.
+    pub jitter: Stats,
+    /// Ticks that came more than a whole period late.
+    pub missed: u64,
+}
+
+impl FrameStats {
+    /// `fps frame_ms frame_max_ms jitter_ms jitter_max_ms missed output_hz`, as sent on `/stats`.
+    pub fn values(&self) -> [f32; 7] {
+        [
+            self.fps,
+            self.frame.mean() * 1000.0,
+            self.frame.max * 1000.0,
+            self.jitter.std_dev() * 1000.0,
+            self.jitter.max * 1000.0,
+            self.missed as f32,
+            self.output_hz,
+        ]
+    }
+}
+
+pub struct Scheduler {
+    /// Frames rendered per second.
+    pub render_rate: f32,
+    /// DMX packets sent per second while the output is live. A frame that
+    /// changed is sent straight away without waiting for the next tick.
+    pub output_rate: f32,
+    /// Once the output hasn't changed for this long it is only resent this
+    /// often, set from the sACN keepalive.
+    pub refresh: Duration,
+    /// How often statistics are logged.
+    pub report: Duration,
+
+    began: Option<Instant>,
+    last_tick: Option<f32>,
+    sent: Option<(Vec<u8>, f32)>,
+    changed: f32,
+    sends: u64,
+    current: FrameStats,
+    period_start: f32,
+    /// The last completed report period.
+    pub stats: FrameStats,
+}
+
+impl Scheduler {
+    pub fn new(render_rate: f32, output_rate: f32) -> Self {
+        Self {
+            render_rate,
+            output_rate,
+            refresh: Duration::from_secs(1),
+            report: Duration::from_secs(10),
+
+            began: None,
+            last_tick: None,
+            sent: None,
+            changed: 0.0,
+            sends: 0,
+            current: FrameStats::default(),
+            period_start: 0.0,
+            stats: FrameStats::default(),
+        }
+    }
+
+    /// Rates from `MSL_FPS` and `MSL_DMX_RATE` in Hz, defaulting to 200 and 44.
+    pub fn from_env() -> Self {
+        let rate = |name: &str, default: f32| match std::env::var(name).ok().map(|s| s.parse::<f32>()) {
+            Some(Ok(rate)) if rate > 0.0 => rate,
+            Some(_) => {
+                log::warn!("Invalid {}, using {}Hz", name, default);
+                default
+            },
+            None => default,
+        };
+        Self::new(rate("MSL_FPS", 200.0), rate("MSL_DMX_RATE", 44.0))
+    }
+
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.render_rate)
+    }
+
+    /// Start of a frame at clock time `now`.
+    pub fn begin(&mut self, now: f32) {
+        self.began = Some(Instant::now());
+        let period = 1.0 / self.render_rate;
+        if let Some(last) = self.last_tick {
+            let late = now - last - period;
+            self.current.jitter.record(late.abs());
+            if late > period {
+                self.current.missed += 1;
+            }
+        }
+        self.last_tick = Some(now);
+    }
+
+    /// End of the frame started by `begin`, logging statistics once a report period is over.
+    pub fn end(&mut self, now: f32) {
+        if let Some(began) = self.began.take() {
+            self.current.frame.record(began.elapsed().as_secs_f32());
+        }
+
+        let elapsed = now - self.period_start;
+        if elapsed >= self.report.as_secs_f32() {
+            let mut stats = std::mem::take(&mut self.current);
+            stats.fps = stats.frame.count as f32 / elapsed;
+            stats.output_hz = std::mem::take(&mut self.sends) as f32 / elapsed;
+            let [fps, frame, frame_max, jitter, jitter_max, missed, output_hz] = stats.values();
+            log::info!(
+                "{:.1}fps, frame {:.2}ms (max {:.2}ms), jitter {:.2}ms (max {:.2}ms), {} missed, DMX {:.1}Hz",
+                fps, frame, frame_max, jitter, jitter_max, missed, output_hz,
+            );
+            self.stats = stats;
+            self.period_start = now;
+        }
+    }
+
+    /// Whether `buffer` should be sent at `now`: it changed, or an output tick
+    /// has passed. Output that has been static for `refresh` only ticks once
+    /// per `refresh`.
+    pub fn output_due(&mut self, now: f32, buffer: &[u8]) -> bool {
+        let due = match &self.sent {
+            Some((sent, _)) if sent != buffer => {
+                self.changed = now;
+                true
+            },
+            Some((_, at)) => {
+                let refresh = self.refresh.as_secs_f32();
+                let tick = if now - self.changed < refresh { 1.0 / self.output_rate } else { refresh };
+                now - at >= tick
+            },
+            None => {
+                self.changed = now;
+                true
+            },
+        };
+        if due {
+            self.sent = Some((buffer.to_vec(), now));
+            self.sends += 1;
+        }
+        due
+    }
+}
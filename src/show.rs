@@ -0,0 +1,678 @@
+use stagebridge::midi::device::launch_control_xl::types::{
+    Brightness as CtrlBrightness, Color as CtrlColor,
+};
+use stagebridge::midi::device::launch_control_xl::Input as CtrlInput;
+use stagebridge::midi::device::launchpad_x::types::{Coord, Pos};
+use stagebridge::midi::device::launchpad_x::{Input as PadInput, LaunchpadX};
+use stagebridge::midi::Midi;
+
+type Pad = Midi<LaunchpadX>;
+
+use crate::*;
+use crate::logic::{CtrlLed, CtrlLeds, Logic};
+use crate::mapping::{self, Action, Button, Fader};
+
+/// Scene names by grid position, `SCENES[y][x]`, as laid out in pad.txt.
+pub const SCENES: [[&str; 8]; 8] = [
+    ["beat0-16th", "beat0-8th", "beat0-4th", "beat0-half", "beat1-16th", "beat1-8th", "beat1-4th", "beat1-half"],
+    ["red", "red-blue", "red-violet", "magenta", "lime", "yellow", "pea-lime", "mint"],
+    ["blue", "cyan-blue", "blue-violet", "blue-mint", "rgb", "white", "rainbow-slow", "rainbow"],
+    ["low-0", "low-1", "low-2", "low-3", "break-off", "break-spot", "break-cross", "break-roll"],
+    ["move-0", "move-1", "move-2", "move-3", "break-dim", "break-solid", "break-strobe", "break-strobe-fast"],
+    ["build-color-0", "build-color-1", "build-color-2", "build-color-3", "drop-tri-0", "drop-tri-1", "drop-tri-2", "drop-tri-3"],
+    ["build-white-0", "build-white-1", "white-roll", "mega-white-roll", "drop-wave", "drop-snap", "drop-pulse", "drop-mega-pulse"],
+    ["", "", "", "", "lasers-off", "lasers-line", "lasers-penta", "lasers-wing"],
+];
+
+/// Base colors `(color0, color1)` of the color scenes in rows 1 and 2,
+/// `PALETTES[y - 1][x]`, as laid out in pad.txt.
+pub const PALETTES: [[(ColorOp, ColorOp); 8]; 2] = {
+    use ColorOp::{Rainbow, Value};
+    [
+        [
+            (Value(Color::RED), Value(Color::RED)),
+            (Value(Color::RED), Value(Color::BLUE)),
+            (Value(Color::RED), Value(Color::VIOLET)),
+            (Value(Color::MAGENTA), Value(Color::MAGENTA)),
+            (Value(Color::LIME), Value(Color::LIME)),
+            (Value(Color::YELLOW), Value(Color::YELLOW)),
+            (Value(Color::PEA), Value(Color::LIME)),
+            (Value(Color::MINT), Value(Color::MINT)),
+        ],
+        [
+            (Value(Color::BLUE), Value(Color::BLUE)),
+            (Value(Color::CYAN), Value(Color::BLUE)),
+            (Value(Color::BLUE), Value(Color::VIOLET)),
+            (Value(Color::BLUE), Value(Color::MINT)),
+            (Value(Color::RGB), Value(Color::RGB)),
+            (Value(Color::WHITE), Value(Color::WHITE)),
+            (Rainbow { pd: Pd(16, 1) }, Rainbow { pd: Pd(16, 1) }),
+            (Rainbow { pd: Pd(4, 1) }, Rainbow { pd: Pd(4, 1) }),
+        ],
+    ]
+};
+
+/// Grid position of the scene called `name`.
+pub fn scene_pos(name: &str) -> Option<(u8, u8)> {
+    SCENES.iter().enumerate().find_map(|(y, row)| {
+        let x = row.iter().position(|&s| !s.is_empty() && s == name)?;
+        Some((x as u8, y as u8))
+    })
+}
+
+/// The show state and all of the logic driving it, independent of any
+/// controllers or outputs.
+pub struct Show {
+    pub state: State,
+
+    pub time: logic::Time,
+    pub pads: logic::Pads,
+    pub beams: logic::Beams,
+    pub lasers: logic::Lasers,
+    pub bars: logic::Bars,
+    pub pars: logic::Pars,
+    pub spiders: logic::Spiders,
+    pub strobes: logic::Strobes,
+    pub sequencer: logic::Sequencer,
+    pub playback: logic::Playback,
+    pub stages: logic::Stages,
+
+    /// Crossfade in progress: the lights being faded from, the loop time `t0`
+    /// it started at and its length in beats. Loop time keeps moving whatever
+    /// the clock source does, so a fade can't stall or run backwards.
+    fade: Option<(Lights, f32, f32)>,
+    last: Lights,
+
+    /// Grid position of the last triggered scene.
+    pub current: Option<(u8, u8)>,
+
+    /// Set when a scene wants the visualizer to beat immediately.
+    pub viz_trigger: bool,
+
+    /// Armed MIDI learn target, bound to the next control used on a mapped controller.
+    pub learn: Option<mapping::Target>,
+}
+
+impl Show {
+    pub fn new() -> Self {
+        Self {
+            state: State::default(),
+
+            time: logic::Time::new(),
+            pads: logic::Pads::new(),
+            beams: logic::Beams::new(),
+            lasers: logic::Lasers::new(),
+            bars: logic::Bars::new(),
+            pars: logic::Pars::new(),
+            spiders: logic::Spiders::new(),
+            strobes: logic::Strobes::new(),
+            sequencer: logic::Sequencer::new(),
+            playback: logic::Playback::new(logic::CueList::default()),
+            stages: logic::Stages::new(logic::StageConfig::default()),
+
+            fade: None,
+            last: Lights::default(),
+
+            current: None,
+
+            viz_trigger: false,
+            learn: None,
+        }
+    }
+
+    fn logic(&self) -> [&dyn Logic; 11] {
+        [
+            &self.time,
+            &self.pads,
+            &self.beams,
+            &self.lasers,
+            &self.bars,
+            &self.pars,
+            &self.spiders,
+            &self.strobes,
+            &self.sequencer,
+            &self.playback,
+            &self.stages,
+        ]
+    }
+
+    /// Trigger the scene at grid position (x, y), as laid out in pad.txt.
+    pub fn scene(&mut self, x: u8, y: u8) {
+        if x > 7 || y > 7 {
+            return;
+        }
+        self.current = Some((x, y));
+        let Self {
+            state,
+            time,
+            pads,
+            beams,
+            lasers,
+            bars,
+            pars,
+            spiders,
+            strobes,
+            sequencer,
+            viz_trigger,
+            ..
+        } = self;
+
+        if (3..=6).contains(&y) {
+            sequencer.select(x, y);
+        }
+        match (x, y) {
+            // manual
+            (0, 0) => {
+                state.viz_beat = false;
+                state.viz_pd = Pd(1, 4);
+                *viz_trigger = true;
+                map0!(fx::once(Pd(1, 4), fx::ramp(Pd(1, 4))));
+                state.retrigger(ColorSelect::Color0);
+            },
+            (1, 0) => {
+                state.viz_beat = false;
+                state.viz_pd = Pd(1, 2);
+                *viz_trigger = true;
+                map0!(fx::once(Pd(1, 2), fx::ramp(Pd(1, 2))));
+                state.retrigger(ColorSelect::Color0);
+            },
+            (2, 0) => {
+                state.viz_beat = false;
+                state.viz_pd = Pd(1, 1);
+                *viz_trigger = true;
+                map0!(fx::once(Pd(1, 1), fx::ramp(Pd(1, 1))));
+                state.retrigger(ColorSelect::Color0);
+            },
+            (3, 0) => {
+                state.viz_beat = false;
+                state.viz_pd = Pd(2, 1);
+                *viz_trigger = true;
+                map0!(fx::once(Pd(2, 1), fx::ramp(Pd(2, 1))));
+                state.retrigger(ColorSelect::Color0);
+            },
+            (4, 0) => {
+                map1!(fx::once(Pd(1, 4), fx::ramp(Pd(1, 4))));
+                state.retrigger(ColorSelect::Color1);
+            },
+            (5, 0) => {
+                map1!(fx::once(Pd(1, 2), fx::ramp(Pd(1, 2))));
+                state.retrigger(ColorSelect::Color1);
+            },
+            (6, 0) => {
+                map1!(fx::once(Pd(1, 1), fx::ramp(Pd(1, 1))));
+                state.retrigger(ColorSelect::Color1);
+            },
+            (7, 0) => {
+                map1!(fx::once(Pd(2, 1), fx::ramp(Pd(2, 1))));
+                state.retrigger(ColorSelect::Color1);
+            },
+
+            // colorz
+            (i, 1 | 2) => {
+                match (i, y) {
+                    (0..=3, 1) => { r!(); },
+                    (_, 1) => { g!(); },
+                    (0..=3, 2) => { b!(); },
+                    _ => { o!(); },
+                }
+                let (c0, c1) = PALETTES[y as usize - 1][i as usize].clone();
+                color0!(c0);
+                color1!(c1);
+            },
+
+            // low
+            (0, 3) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.2, 0.15));
+                map1!(fx::off());
+                beams.pattern = BeamPattern::WaveY { pd: Pd(8, 1) };
+                pars.color = ParColor::Color1;
+                spiders.color = SpiderColor::Color1;
+                state.viz_beat = false;
+            },
+            (1, 3) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.2, 0.15));
+                map1!(fx::off());
+                beams.pattern = BeamPattern::WaveY { pd: Pd(8, 1) };
+                pars.color = ParColor::Color1;
+                state.viz_beat = false;
+            },
+            (2, 3) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.2, 0.15));
+                map1!(fx::off());
+                beams.color = BeamColor::Color1;
+                spiders.color = SpiderColor::Color1;
+                state.viz_beat = false;
+            },
+            (3, 3) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.2, 0.15));
+                map1!(fx::off());
+                beams.color = BeamColor::Color1;
+                state.viz_beat = false;
+            },
+            (0, 4) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                map1!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::SpreadOut;
+                spiders.color = SpiderColor::Color1;
+                spiders.pattern = SpiderPattern::Up;
+                state.viz_beat = false;
+            },
+            (1, 4) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                map1!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::SpreadOut;
+                spiders.color = SpiderColor::Color1;
+                spiders.pattern = SpiderPattern::Alternate { pd: Pd(8, 1) };
+                state.viz_beat = false;
+            },
+            (2, 4) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.6, 0.2));
+                map1!(fx::sin(Pd(8, 1), 0.6, 0.2));
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::WaveY { pd: Pd(8, 1) };
+                spiders.color = SpiderColor::Color1;
+                spiders.pattern = SpiderPattern::Alternate { pd: Pd(8, 1) };
+                state.viz_beat = false;
+            },
+            (3, 4) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.6, 0.2));
+                map1!(fx::sin(Pd(8, 1), 0.6, 0.2));
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Out;
+                spiders.color = SpiderColor::Color1;
+                spiders.pattern = SpiderPattern::Alternate { pd: Pd(8, 1) };
+                state.viz_beat = false;
+            },
+
+            // build
+            (0, 5) => {
+                // 2/1 short pulse beams
+                reset!();
+                map0!(fx::pulse_short(Pd(1, 1), 1.0..0.0));
+                map1!(fx::off());
+                spiders.color = SpiderColor::Off;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Out;
+                state.viz_alpha = 0.0;
+            },
+            (1, 5) => {
+                // 2/1 short pulse beams, 1/1 pars strobe
+                reset!();
+                map0!(fx::pulse_short(Pd(1, 2), 1.0..0.0));
+                map1!(fx::off());
+                spiders.color = SpiderColor::Off;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Out;
+                state.viz_alpha = 0.0;
+            },
+            (2, 5) => {
+                // 2/1 short pulse beams, 1/2 pars strobe
+                reset!();
+                map0!(fx::strobe(Pd(1, 4), 0.5, 0.0..1.0));
+                map1!(fx::alpha(0.1));
+                spiders.color = SpiderColor::Off;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Out;
+                bars.color = BarColor::Color0;
+                strobes.color = StrobeColor::Strobe { pd: Pd(1, 4), duty: 0.1, alpha: 1.0 };
+                state.viz_alpha = 0.0;
+            },
+            (3, 5) => {
+                // 2/1 short pulse beams, 1/2 pars strobe, 1/4 strobe light
+                reset!();
+                map0!(fx::strobe(Pd(1, 8), 0.4, 0.0..1.0));
+                map1!(fx::alpha(0.5));
+                spiders.color = SpiderColor::Off;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Out;
+                bars.color = BarColor::Color0;
+                strobes.color = StrobeColor::Strobe { pd: Pd(1, 4), duty: 0.1, alpha: 1.0 };
+                state.viz_alpha = 0.0;
+            },
+            (0, 6) => {
+                // 2/1 short pulse beams
+                reset!();
+                map0!(fx::off());
+                map1!(ColorMapOp::value(Color::WHITE).compose(fx::pulse_short(Pd(2, 1), 0.8..0.0)));
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_alpha = 0.0;
+            },
+            (1, 6) => {
+                // 2/1 short pulse beams, 1/1 pars strobe
+                reset!();
+                map0!(ColorMapOp::value(Color::WHITE).compose(fx::strobe(Pd(1, 1), 0.1, 0.0..0.2)));
+                map1!(ColorMapOp::value(Color::WHITE).compose(fx::pulse_short(Pd(2, 1), 0.8..0.0)));
+                spiders.color = SpiderColor::Off;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_alpha = 0.0;
+            },
+            (2, 6) => {
+                // white roll
+                reset!();
+                map0!(fx::off());
+                map1!(Color::WHITE.into());
+                beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+                beams.color = BeamColor::Roll { pd: Pd(1, 1), duty: 0.1, offset: 0.1, alpha: 1.0 };
+                pars.color = ParColor::StrobeAlt1 { pd: Pd(1, 1), duty: 0.1 };
+                strobes.color = StrobeColor::Strobe { pd: Pd(1, 2), duty: 0.1, alpha: 1.0 };
+                state.viz_alpha = 0.0;
+            },
+            (3, 6) => {
+                // mega white roll
+                reset!();
+                map0!(fx::off());
+                map1!(Color::WHITE.into());
+                beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+                beams.color = BeamColor::Roll { pd: Pd(1, 1), duty: 0.25, offset: 0.1, alpha: 1.0 };
+                pars.color = ParColor::StrobeRoll1 { pd: Pd(1, 1), duty: 0.2, offset: 0.3 };
+                strobes.color = StrobeColor::Strobe { pd: Pd(1, 4), duty: 0.5, alpha: 1.0 };
+                state.viz_alpha = 0.0;
+            },
+
+            // break
+            (4, 3) => {
+                reset!();
+                map0!(fx::off());
+                map1!(fx::off());
+            },
+            (5, 3) => {
+                reset!();
+                map0!(fx::off());
+                map1!(fx::alpha(0.1));
+                pars.color = ParColor::Spotlight;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::SpreadIn;
+                state.viz_alpha = 0.0;
+            },
+            (6, 3) => {
+                reset!();
+                map0!(fx::off());
+                map1!(fx::alpha(0.1));
+                pars.color = ParColor::UpDown;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Cross;
+                state.viz_alpha = 0.0;
+            },
+            (7, 3) => {
+                reset!();
+                map0!(fx::off());
+                map1!(fx::alpha(0.1));
+                pars.color = ParColor::UpDown;
+                beams.pattern = BeamPattern::Cross;
+                beams.color = BeamColor::Roll { pd: Pd(1, 1), duty: 0.75, offset: 0.1, alpha: 0.2 };
+                strobes.color = StrobeColor::Strobe { pd: Pd(1, 2), duty: 0.25, alpha: 0.2 };
+                state.viz_alpha = 0.0;
+            },
+            (4, 4) => {
+                reset!();
+                map0!(fx::alpha(0.25));
+                map1!(fx::off());
+                beams.color = BeamColor::Color1;
+                state.viz_alpha = 0.0;
+            },
+            (5, 4) => {
+                reset!();
+                map0!(fx::alpha(0.5));
+                map1!(fx::alpha(0.5));
+                beams.pattern = BeamPattern::Out;
+                strobes.color = StrobeColor::Color0;
+                state.viz_alpha = 0.0;
+            },
+            (6, 4) => {
+                reset!();
+                map0!(fx::strobe(Pd(1, 2), 0.5, 0.0..1.0));
+                map1!(fx::off());
+                strobes.color = StrobeColor::Color0;
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                state.viz_alpha = 0.0;
+            },
+            (7, 4) => {
+                reset!();
+                map0!(fx::strobe(Pd(1, 4), 0.5, 0.0..1.0));
+                map1!(fx::id());
+                strobes.color = StrobeColor::Color0;
+                spiders.color = SpiderColor::Color1;
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_alpha = 0.0;
+            },
+
+            // drop
+            (4, 5) => {
+                // solid with tri color1
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                map1!(fx::tri(Pd(2, 1), 0.0..1.0));
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (5, 5) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.3, 0.2));
+                map1!(fx::tri(Pd(1, 1), 0.0..1.0));
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (6, 5) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.25, 0.25));
+                map1!(fx::tri(Pd(1, 1), 0.0..1.0));
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (7, 5) => {
+                reset!();
+                map0!(fx::sin(Pd(8, 1), 0.25, 0.25));
+                map1!(fx::tri(Pd(1, 2), 0.0..1.0));
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_pd = Pd(1, 2);
+            },
+            (4, 6) => {
+                // wave slow
+                reset!();
+                map0!(fx::pulse(Pd(1, 1), 1.0..0.0));
+                map1!(fx::id());
+                spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(2, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (5, 6) => {
+                // snap slow
+                reset!();
+                map0!(fx::pulse(Pd(1, 1), 1.0..0.0));
+                map1!(fx::id());
+                spiders.pattern = SpiderPattern::Snap { pd: Pd(2, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (6, 6) => {
+                // mini short pulse
+                reset!();
+                map0!(fx::pulse(Pd(1, 1), 1.0..0.0));
+                map1!(fx::id());
+                spiders.pattern = SpiderPattern::Snap { pd: Pd(1, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+                state.viz_pd = Pd(1, 1);
+            },
+            (7, 6) => {
+                // mega short pulse
+                reset!();
+                map0!(fx::pulse(Pd(1, 2), 1.0..0.0));
+                map1!(fx::id());
+                spiders.pattern = SpiderPattern::Snap { pd: Pd(1, 1) };
+                spiders.color = SpiderColor::Color1;
+                beams.color = BeamColor::Color1;
+                beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+                strobes.color = StrobeColor::Color0;
+                state.viz_pd = Pd(1, 2);
+            },
+
+            // lazors
+            (4, 7) => {
+                lasers.active = false;
+            },
+            (5, 7) => {
+                lasers.active = true;
+                lasers.pattern = LaserPattern::Line2X;
+                lasers.color = state.color_mode.into();
+                lasers.pos = LaserPos::WaveY { pd: Pd(8, 1) }
+            },
+            (6, 7) => {
+                lasers.active = true;
+                lasers.pattern = LaserPattern::LinePenta;
+                lasers.color = state.color_mode.into();
+                lasers.pos = LaserPos::Rotate { pd: Pd(8, 1) }
+            },
+            (7, 7) => {
+                lasers.active = true;
+                lasers.pattern = LaserPattern::TriWing;
+                lasers.color = state.color_mode.into();
+                lasers.pos = LaserPos::Rotate { pd: Pd(8, 1) }
+            }
+
+            _ => {}
+        };
+        log::debug!("map0: {:?}, map1: {:?}", state.map0, state.map1);
+    }
+
+    pub fn pad(&mut self, input: PadInput) {
+        if let Some(action) = mapping::launchpad(input) {
+            self.action(action);
+        }
+    }
+
+    pub fn ctrl(&mut self, input: CtrlInput) {
+        if let Some(action) = mapping::launch_control(input) {
+            self.action(action);
+        }
+    }
+
+    pub fn action(&mut self, action: Action) {
+        match action {
+            Action::Grid(x, y, _) if !self.sequencer.editing => self.scene(x, y),
+            Action::Scene(x, y) => self.scene(x, y),
+            Action::Fader(Fader::Alpha, fr) => self.state.alpha = fr,
+            Action::Fader(Fader::Fr0, fr) => self.state.fr0 = fr,
+            Action::Fader(Fader::Fr1, fr) => self.state.fr1 = fr,
+            Action::Fader(Fader::Group(group), fr) => self.state.group_alpha[group as usize] = fr,
+            Action::Button(Button::Blackout) => self.state.off = !self.state.off,
+            _ => {}
+        }
+
+        let state = &mut self.state;
+        self.time.action(state, action);
+        self.pads.action(state, action);
+        self.beams.action(state, action);
+        self.lasers.action(state, action);
+        self.bars.action(state, action);
+        self.pars.action(state, action);
+        self.spiders.action(state, action);
+        self.strobes.action(state, action);
+        self.sequencer.action(state, action);
+        self.playback.action(state, action);
+        self.stages.action(state, action);
+    }
+
+    /// Move to the time of a new frame from the loop's `Clock`, which also
+    /// drives the static clock source.
+    pub fn tick(&mut self, t: f32) {
+        let state = &mut self.state;
+        state.t0 = t;
+        if let ClockSource::Static { bpm } = self.time.source {
+            state.t = t;
+            state.phi = (t * (bpm / 60.0)).fmod(16.0);
+        }
+    }
+
+    /// Render the current frame. Pure apart from firing due cues and starting
+    /// newly installed one-shots.
+    pub fn render(&mut self) -> Lights {
+        if let Some(cue) = self.playback.poll(&self.state) {
+            if cue.fade > 0.0 {
+                self.fade = Some((self.last.clone(), self.state.t0, cue.fade));
+            }
+            self.scene(cue.scene.0, cue.scene.1);
+        }
+        self.state.arm();
+
+        self.pads.active = self.current;
+        self.pads.queued = self.playback.queued().into_iter().collect();
+        self.pads.hidden = self.sequencer.editing;
+
+        let mut lights = Lights::default();
+        for logic in self.logic() {
+            logic.render(&self.state, &mut lights);
+        }
+
+        if self.state.off {
+            lights.brightness(0.0);
+        } else {
+            lights.brightness(self.state.alpha);
+        }
+        for (group, fr) in Group::ALL.into_iter().zip(self.state.group_alpha) {
+            lights.group_brightness(group, fr);
+        }
+
+        if let Some((from, t, beats)) = &self.fade {
+            let fr = (self.state.t0 - t) * (self.state.bpm / 60.0) / beats;
+            if fr < 1.0 {
+                lights = from.mix(&lights, fr.max(0.0));
+            } else {
+                self.fade = None;
+            }
+        }
+        self.last = lights.clone();
+        lights
+    }
+
+    /// Launch Control LEDs for the current frame.
+    pub fn leds(&self) -> CtrlLeds {
+        let mut leds = CtrlLeds::new();
+        for logic in self.logic() {
+            logic.leds(&self.state, &mut leds);
+        }
+        if self.state.off {
+            leds.insert(CtrlLed::Control(7), (CtrlColor::Red, CtrlBrightness::High));
+        }
+        leds
+    }
+
+    /// Send Launchpad feedback for the current frame.
+    pub async fn feedback(&self, pad: &Pad) {
+        for logic in self.logic() {
+            logic.feedback(&self.state, pad).await;
+        }
+    }
+}
@@ -0,0 +1,814 @@
+//! Snapshot tests for logic renders and fixture encoding. Nothing here needs
+//! MIDI or the network, so they run anywhere with `cargo test`.
+//!
+//! Snapshots are kept in `src/snapshots`. After an intended change, review
+//! and accept the new output with `cargo insta review`. Audio fixtures are
+//! kept in `src/fixtures`.
+
+use std::fmt::Write;
+
+use insta::assert_snapshot;
+use stagebridge::dmx::{DMXDevice, DMX};
+
+use crate::*;
+
+/// A show state at `phi` beats into the phrase, with red and blue as base colors.
+fn state(phi: f32, bpm: f32) -> State {
+    let mut state = State::default();
+    state.t0 = phi * 60.0 / bpm;
+    state.t = state.t0;
+    state.phi = phi;
+    state.bpm = bpm;
+    state.color0 = ColorOp::value(Color::RED);
+    state.color1 = ColorOp::value(Color::BLUE);
+    state
+}
+
+/// The lights from one logic alone, and the DMX universe they encode to.
+fn render(logic: &dyn Logic, state: &State) -> (Lights, Vec<u8>) {
+    let mut lights = Lights::default();
+    logic.render(state, &mut lights);
+    let mut dmx = DMX::new(205);
+    lights.write(&mut dmx);
+    (lights, dmx.buffer().to_vec())
+}
+
+/// DMX as rows of 16 channels, numbered from 1.
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, row) in bytes.chunks(16).enumerate() {
+        let _ = write!(out, "{:3}:", i * 16 + 1);
+        for byte in row {
+            let _ = write!(out, " {:02x}", byte);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Render `logic` at each phase and format everything for a snapshot.
+fn snapshot(logic: &dyn Logic, phis: &[f32]) -> String {
+    let mut out = String::new();
+    for &phi in phis {
+        let (lights, dmx) = render(logic, &state(phi, 120.0));
+        let _ = writeln!(out, "phi = {}\n{:#?}\n{}", phi, lights, hex(&dmx));
+    }
+    out
+}
+
+const PHIS: [f32; 4] = [0.0, 0.5, 1.25, 3.75];
+
+#[test]
+fn beams_square() {
+    let mut beams = Beams::new();
+    beams.pattern = BeamPattern::Square { pd: Pd(1, 1) };
+    beams.color = BeamColor::Alternate;
+    assert_snapshot!(snapshot(&beams, &PHIS));
+}
+
+#[test]
+fn beams_wave_y() {
+    let mut beams = Beams::new();
+    beams.pattern = BeamPattern::WaveY { pd: Pd(2, 1) };
+    beams.color = BeamColor::Color0;
+    assert_snapshot!(snapshot(&beams, &PHIS));
+}
+
+#[test]
+fn pars_up_down() {
+    let mut pars = Pars::new();
+    pars.color = ParColor::UpDown;
+    assert_snapshot!(snapshot(&pars, &[0.0]));
+}
+
+/// The spotlight pars are picked by position, and land on the same two as before.
+#[test]
+fn pars_spotlight() {
+    let mut pars = Pars::new();
+    pars.color = ParColor::Spotlight;
+    let (lights, _) = render(&pars, &state(0.0, 120.0));
+    let spots = lights.pars.iter()
+        .enumerate()
+        .filter(|(_, par)| par.color == Color::BLUE)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(spots, [3, 6]);
+}
+
+#[test]
+fn pars_roll() {
+    let mut pars = Pars::new();
+    pars.color = ParColor::Roll { pd: Pd(1, 1), ofs: 0.0 };
+    assert_snapshot!(snapshot(&pars, &PHIS));
+}
+
+#[test]
+fn pars_sweep() {
+    let mut pars = Pars::new();
+    pars.color = ParColor::Sweep { pd: Pd(2, 1), shape: Shape::CenterOut, duty: 0.25, spread: 0.5 };
+    assert_snapshot!(snapshot(&pars, &PHIS));
+}
+
+#[test]
+fn bars_roll() {
+    let mut bars = Bars::new();
+    bars.color = BarColor::Roll { pd: Pd(1, 1), duty: 0.5, offset: 0.25 };
+    assert_snapshot!(snapshot(&bars, &PHIS));
+}
+
+#[test]
+fn spiders_wave() {
+    let mut spiders = Spiders::new();
+    spiders.color = SpiderColor::Both;
+    spiders.pattern = SpiderPattern::Wave { pd: Pd(2, 1) };
+    assert_snapshot!(snapshot(&spiders, &PHIS));
+}
+
+#[test]
+fn strobes_strobe() {
+    let mut strobes = Strobes::new();
+    strobes.color = StrobeColor::Strobe { pd: Pd(1, 4), duty: 0.5, alpha: 1.0 };
+    assert_snapshot!(snapshot(&strobes, &PHIS));
+}
+
+/// A full scene through `Show`, including masters and submasters.
+#[test]
+fn show_drop_pulse() {
+    let mut show = Show::new();
+    let (x, y) = scene_pos("drop-pulse").unwrap();
+    show.scene(x, y);
+    show.state.alpha = 0.8;
+    show.state.group_alpha[Group::Pars as usize] = 0.5;
+
+    let mut out = String::new();
+    let mut dmx = DMX::new(205);
+    for phi in PHIS {
+        show.state.phi = phi;
+        show.state.t = phi * 60.0 / show.state.bpm;
+        let lights = show.render();
+        lights.write(&mut dmx);
+        let _ = writeln!(out, "phi = {}\n{}", phi, hex(dmx.buffer()));
+    }
+    assert_snapshot!(out);
+}
+
+/// Channel layout of every fixture type, with distinct values per channel.
+#[test]
+fn encode() {
+    let color = Color::argbw(1.0, 0.2, 0.4, 0.6, 0.8);
+    let lights = Lights::default().all(color);
+
+    let mut out = String::new();
+    let mut encode = |name: &str, device: &dyn DMXDevice| {
+        let mut buffer = vec![0; device.size()];
+        device.encode(&mut buffer);
+        let _ = writeln!(out, "{}: {:?}", name, buffer);
+    };
+    encode("par", &lights.pars[0]);
+    encode("beam", &lights.beams[0]);
+    encode("strobe", &lights.strobe);
+    encode("bar", &lights.bars[0]);
+    encode("spider", &lights.spiders[0]);
+    encode("laser", &lights.laser);
+    assert_snapshot!(out);
+}
+
+/// Every fixture lands at its patched address.
+#[test]
+fn patch() {
+    let lights = Lights::default().all(Color::WHITE);
+    let mut dmx = DMX::new(205);
+    lights.write(&mut dmx);
+    assert_snapshot!(hex(dmx.buffer()));
+}
+
+/// Offline renders on the same frame times match, whichever clock supplies them.
+#[tokio::test]
+async fn replay_matches_step() {
+    let render = render::Render {
+        tempo: render::Tempo::Bpm(128.0),
+        scenes: vec![render::SceneAt { beat: 0.0, x: 6, y: 6 }, render::SceneAt { beat: 4.0, x: 5, y: 3 }],
+    };
+
+    let mut step = vec![];
+    let mut times = vec![];
+    render.run(&mut StepClock::new(0.01, Some(4.0)), |t, _, dmx| {
+        times.push(t);
+        step.push(dmx.to_vec());
+        Ok(())
+    }).await.unwrap();
+
+    let mut replay = vec![];
+    render.run(&mut ReplayClock::new(times), |_, _, dmx| {
+        replay.push(dmx.to_vec());
+        Ok(())
+    }).await.unwrap();
+
+    assert_eq!(step.len(), 400);
+    assert_eq!(step, replay);
+}
+
+/// Click track at 120 BPM with the first click at 0.1s: 8s of 16 bit, 22050Hz mono.
+const CLICK_120: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/fixtures/click-120.wav");
+
+/// The beat tracker locks to a click track's tempo and lands its beats on the clicks.
+#[test]
+fn audio_click_track() {
+    let beats = audio::analyze_wav(CLICK_120).unwrap();
+    assert!(!beats.is_empty(), "no tempo found");
+    for beat in &beats {
+        assert!((beat.bpm - 120.0).abs() < 1.0, "{}s: bpm {}", beat.t, beat.bpm);
+
+        // Beats are counted from when tracking starts, so only the phase within a beat is fixed.
+        let expected = ((beat.t - 0.1) * 2.0).rem_euclid(1.0);
+        let err = (beat.phi.fract() - expected + 0.5).rem_euclid(1.0) - 0.5;
+        assert!(err.abs() < 0.1, "{}s: phase {} expected {}", beat.t, beat.phi.fract(), expected);
+    }
+}
+
+/// The web surface serves the scene grid over HTTP, takes commands on its
+/// WebSocket, and pushes status back once the show renders.
+#[tokio::test]
+async fn web_control() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time::{sleep, timeout, Duration};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut web = web::Web::spawn("127.0.0.1:0".parse().unwrap());
+
+    let mut http = tokio::net::TcpStream::connect(web.addr).await.unwrap();
+    http.write_all(b"GET /scenes HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    http.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("\"drop-pulse\""), "{}", response);
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", web.addr)).await.unwrap();
+    ws.send(Message::Text(r#"{"addr": "/scene", "args": ["drop-pulse"]}"#.into())).await.unwrap();
+
+    let cmd = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(cmd) = web.commands().next() {
+                break cmd;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    }).await.expect("no command from the page");
+    assert_eq!(cmd.addr, "/scene");
+
+    let mut show = Show::new();
+    remote::Remote::new().handle(&mut show, &cmd);
+    assert_eq!(show.current, scene_pos("drop-pulse"));
+
+    let lights = show.render();
+    web.publish(&show, &lights);
+    let status = match timeout(Duration::from_secs(5), ws.next()).await {
+        Ok(Some(Ok(Message::Text(json)))) => serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        other => panic!("no status from the server: {:?}", other),
+    };
+    let (x, y) = scene_pos("drop-pulse").unwrap();
+    assert_eq!(status["scene"], serde_json::json!([x, y]));
+    assert_eq!(status["fixtures"].as_array().unwrap().len(), 10 + 4 + 1 + 2 + 2);
+}
+
+/// E1.31 packet layout, checked against the offsets in the standard.
+#[test]
+fn sacn_packets() {
+    let cid = [7; 16];
+    let data = sacn::data_packet(&cid, 3, 150, 9, 42, 0, &[1, 2, 3]);
+    assert_eq!(data.len(), 129);
+    assert_eq!(&data[4..16], b"ASC-E1.17\0\0\0");
+    assert_eq!(&data[16..18], &[0x70, 113]);
+    assert_eq!(&data[38..40], &[0x70, 91]);
+    assert_eq!(&data[115..117], &[0x70, 14]);
+    assert_eq!((data[108], &data[109..111], data[111]), (150, &[0, 9][..], 42));
+    assert_eq!(&data[113..115], &[0, 3]);
+    assert_eq!(&data[123..], &[0, 4, 0, 1, 2, 3]);
+
+    let sync = sacn::sync_packet(&cid, 9, 5);
+    assert_eq!(sync.len(), 49);
+    assert_eq!(&sync[18..22], &[0, 0, 0, 8]);
+    assert_eq!((sync[44], &sync[45..47]), (5, &[0, 9][..]));
+}
+
+/// A profile from data drives any fixture struct's attributes.
+#[test]
+fn custom_profile() {
+    let profile = Profile::parse(r#"
+        name = "rental-par"
+        size = 6
+
+        [[channel]]
+        offset = 0
+        attr = "red"
+
+        [[channel]]
+        offset = 1
+        attr = "green"
+
+        [[channel]]
+        offset = 2
+        attr = "blue"
+
+        [[channel]]
+        offset = 5
+        attr = "pattern"
+        default = 7
+    "#).unwrap();
+
+    let mut buffer = [0; 6];
+    let par = Par { color: Color::argbw(0.5, 1.0, 0.0, 0.0, 0.0) };
+    profile.encode(&par, &mut buffer);
+    assert_eq!(buffer, [Float::byte(0.5f32), 0, 0, 0, 0, 7]);
+}
+
+/// The coarse and fine channels of a 16 bit pair are the two bytes of one value.
+#[test]
+fn fine_profile() {
+    let profile = Profile::parse(r#"
+        name = "fine-pan"
+        size = 2
+
+        [[channel]]
+        offset = 0
+        attr = "pan"
+
+        [[channel]]
+        offset = 1
+        attr = "pan"
+        fine = true
+    "#).unwrap();
+
+    for yaw in [0.0, 0.25, 0.5, 0.999, 1.0] {
+        let mut buffer = [0; 2];
+        profile.encode(&Beam { yaw, ..Beam::default() }, &mut buffer);
+        assert_eq!(u16::from_be_bytes(buffer), (yaw * 65535.0) as u16, "yaw {}", yaw);
+    }
+}
+
+/// An override replaces a built-in profile only if it keeps its footprint.
+#[test]
+fn profile_overrides() {
+    let dir = std::env::temp_dir().join(format!("msl-profiles-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let par = |size: usize| format!("name = \"par\"\nsize = {}\n\n[[channel]]\noffset = 0\nattr = \"red\"\n", size);
+
+    std::fs::write(dir.join("par.toml"), par(8)).unwrap();
+    let loaded = profiles_in(&dir);
+    std::fs::write(dir.join("par.toml"), par(9)).unwrap();
+    let oversized = profiles_in(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded["par"].channels.len(), 1);
+    assert_eq!(loaded["beam"].size, profile("beam").size);
+    assert!(oversized.is_err());
+
+    fn profiles_in(dir: &std::path::Path) -> anyhow::Result<std::collections::BTreeMap<String, Profile>> {
+        lights::load_all(Some(dir))
+    }
+}
+
+/// Every patched fixture fits before the next one and inside the universe.
+#[test]
+fn patch_footprint() {
+    let mut fixtures = vec![];
+    fixtures.extend(PATCH.pars.iter().map(|f| (f.addr, profile("par").size)));
+    fixtures.extend(PATCH.beams.iter().map(|f| (f.addr, profile("beam").size)));
+    fixtures.push((PATCH.strobe.addr, profile("strobe").size));
+    fixtures.extend(PATCH.bars.iter().map(|f| (f.addr, profile("bar").size)));
+    fixtures.push((PATCH.laser.addr, profile("laser").size));
+    fixtures.extend(PATCH.spiders.iter().map(|f| (f.addr, profile("spider").size)));
+    fixtures.sort();
+
+    for pair in fixtures.windows(2) {
+        let [(addr, size), (next, _)] = [pair[0], pair[1]];
+        assert!(addr + size <= next, "fixture at {} overlaps {}", addr, next);
+    }
+    let (addr, size) = fixtures[fixtures.len() - 1];
+    assert!(addr + size - 1 <= 205);
+}
+
+/// Steps advance every `pd`, swing delays the odd steps, and fade crossfades
+/// into the next step at the end of each.
+#[test]
+fn sequencer_steps() {
+    let mut seq = Sequence::new(16);
+    let at = |seq: &Sequence, phi: f32| seq.position(&state(phi, 120.0));
+
+    assert_eq!(at(&seq, 0.0), (0, 1, 0.0));
+    assert_eq!(at(&seq, 0.25), (1, 2, 0.0));
+    assert_eq!(at(&seq, 3.9), (15, 0, 0.0));
+    assert_eq!(at(&seq, 4.25), (1, 2, 0.0));
+
+    seq.swing = 0.25;
+    assert_eq!(at(&seq, 0.25).0, 0);
+    assert_eq!(at(&seq, 0.3125).0, 1);
+    assert_eq!(at(&seq, 0.5).0, 2);
+
+    seq.fade = 0.5;
+    let (i, j, fade) = at(&seq, 0.25);
+    assert_eq!((i, j), (0, 1));
+    assert!((fade - 0.6).abs() < 1e-5, "fade {}", fade);
+
+    seq.swing = 0.0;
+    assert_eq!(at(&seq, 0.0625), (0, 1, 0.0));
+    assert_eq!(at(&seq, 0.1875), (0, 1, 0.5));
+}
+
+/// Sequences programmed from the grid are saved when editing ends and load back.
+#[test]
+fn sequencer_save_load() {
+    use mapping::{Action, Button, Fader};
+
+    let path = std::env::temp_dir().join(format!("msl-sequences-{}.toml", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut state = State::default();
+
+    let mut sequencer = Sequencer::open(&path).unwrap();
+    sequencer.select(6, 6);
+    sequencer.action(&mut state, Action::Button(Button::Edit));
+    sequencer.action(&mut state, Action::Grid(2, 7, 0.5));
+    sequencer.action(&mut state, Action::Grid(2, 7, 1.0));
+    sequencer.action(&mut state, Action::Fader(Fader::Swing, 0.5));
+    sequencer.action(&mut state, Action::Button(Button::Edit));
+
+    let loaded = Sequencer::open(&path);
+    let _ = std::fs::remove_file(&path);
+    let loaded = loaded.unwrap();
+    let seq = &loaded.sequences[&(6, 6)];
+    assert_eq!(seq.steps.len(), 16);
+    assert_eq!(seq.swing, 0.25);
+    assert_eq!(seq.steps[2][0], Some(Cell { color: StepColor::Color1, alpha: 1.0 }));
+    assert_eq!(seq.steps.iter().flatten().filter(|cell| cell.is_some()).count(), 1);
+}
+
+/// The beat-synced generators at known points. Random ones carry on into the
+/// next phrase instead of repeating the last one.
+#[test]
+fn fx_generators() {
+    let at = |beats: f32| {
+        let mut state = state(beats, 120.0);
+        state.phi = beats % 16.0;
+        state
+    };
+    let alpha = |op: &ColorMapOp, beats: f32| op.apply(&at(beats), Color::WHITE).a;
+    let close = |a: f32, b: f32| assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+
+    close(alpha(&saw_up(Pd(1, 1), 0.0..1.0), 0.25), 0.25);
+    close(alpha(&saw_up(Pd(1, 1), 0.5..1.0), 1.5), 0.75);
+    close(alpha(&saw_down(Pd(1, 1), 0.0..1.0), 0.25), 0.75);
+
+    let sh = sample_hold(Pd(1, 1), 7, 0.0..1.0);
+    close(alpha(&sh, 0.25), random(7, 0));
+    close(alpha(&sh, 0.75), random(7, 0));
+    close(alpha(&sh, 1.5), random(7, 1));
+    close(alpha(&sh, 16.5), random(7, 16));
+    assert_ne!(random(7, 16), random(7, 0));
+
+    let noise = noise(Pd(1, 1), 7, 0.0..1.0);
+    close(alpha(&noise, 0.0), random(7, 0));
+    close(alpha(&noise, 0.5), (random(7, 0) + random(7, 1)) / 2.0);
+    close(alpha(&noise, 1.0), random(7, 1));
+    close(alpha(&noise, 16.0), random(7, 16));
+    assert!((alpha(&noise, 15.99) - alpha(&noise, 16.0)).abs() < 0.01, "noise jumps at the phrase");
+
+    let env = Adsr { attack: 0.25, decay: 0.25, sustain: 0.5, release: 0.5, hold: 1.0 };
+    let adsr = adsr(Pd(2, 1), env, 0.0..1.0);
+    for (beats, a) in [(0.125, 0.5), (0.375, 0.75), (0.75, 0.5), (1.25, 0.25), (1.75, 0.0), (2.125, 0.5)] {
+        close(alpha(&adsr, beats), a);
+    }
+}
+
+/// Clones of a one-shot armed at different sites and times run independently,
+/// and retriggering restarts one without touching the other.
+#[test]
+fn once_per_site() {
+    let once = fx::once(Pd(1, 1), fx::ramp(Pd(1, 1)));
+    let mut state = state(0.0, 120.0);
+    let advance = |state: &mut State, phi: f32| {
+        state.phi = phi;
+        state.t0 = phi * 60.0 / state.bpm;
+        state.t = state.t0;
+    };
+
+    state.map0 = once.clone();
+    state.arm();
+    advance(&mut state, 0.5);
+    state.map1 = once.clone();
+    state.arm();
+    assert_eq!(state.map0, state.map1);
+
+    advance(&mut state, 0.75);
+    state.arm();
+    assert!((state.color0().a - 0.25).abs() < 1e-5, "{:?}", state.color0());
+    assert!((state.color1().a - 0.75).abs() < 1e-5, "{:?}", state.color1());
+
+    state.retrigger(ColorSelect::Color0);
+    state.arm();
+    assert!((state.color0().a - 1.0).abs() < 1e-5, "{:?}", state.color0());
+    assert!((state.color1().a - 0.75).abs() < 1e-5, "{:?}", state.color1());
+
+    advance(&mut state, 3.0);
+    assert!(state.color0().a.abs() < 1e-5 && state.color1().a.abs() < 1e-5);
+}
+
+/// Beat maps must move forward, and fixed tempos must be positive.
+#[test]
+fn render_tempo() {
+    let Ok(render::Tempo::Map(beats)) = render::Tempo::parse_map("0.0\n0.5\n\n1.0\n") else { panic!() };
+    assert_eq!(beats, [0.0, 0.5, 1.0]);
+    assert_eq!(render::Tempo::Map(beats).at(0.75), (1.5, 120.0));
+
+    assert!(render::Tempo::parse_map("0.0\n0.5\n0.5\n").is_err());
+    assert!(render::Tempo::parse_map("0.0\n0.5\n0.25\n").is_err());
+    assert!(render::Tempo::parse_map("0.0\ninf\n").is_err());
+    assert!(render::Tempo::parse_map("0.0\n").is_err());
+
+    assert!(render::Tempo::bpm(174.0).is_ok());
+    for bpm in [0.0, -120.0, f32::NAN, f32::INFINITY] {
+        assert!(render::Tempo::bpm(bpm).is_err(), "bpm {}", bpm);
+    }
+}
+
+/// MTC quarter frames assemble into the time latched two frames earlier.
+#[test]
+fn mtc_quarter_frames() {
+    use timecode::{MtcDecoder, Timecode};
+
+    // 01:02:03:04 at 25fps, as eight nibbles.
+    let pieces = [4, 0, 3, 0, 2, 0, 1, 1 << 1];
+    let mut decoder = MtcDecoder::default();
+    for (i, nibble) in pieces.iter().enumerate().take(7) {
+        assert_eq!(decoder.feed(&[0xF1, (i as u8) << 4 | nibble]), None);
+    }
+    let tc = decoder.feed(&[0xF1, 7 << 4 | pieces[7]]);
+    assert_eq!(tc, Some(Timecode { hours: 1, minutes: 2, seconds: 3, frames: 6, fps: 25.0 }));
+
+    // A sequence joined halfway waits for the next full set.
+    assert_eq!(decoder.feed(&[0xF1, 6 << 4 | pieces[6]]), None);
+    assert_eq!(decoder.feed(&[0xF1, 7 << 4 | pieces[7]]), None);
+}
+
+/// MTC full-frame messages give the time directly.
+#[test]
+fn mtc_full_frame() {
+    use timecode::{MtcDecoder, Timecode};
+
+    let mut decoder = MtcDecoder::default();
+    let tc = decoder.feed(&[0xF0, 0x7F, 0x7F, 0x01, 0x01, 3 << 5 | 1, 2, 3, 4, 0xF7]);
+    assert_eq!(tc, Some(Timecode { hours: 1, minutes: 2, seconds: 3, frames: 4, fps: 30.0 }));
+    assert_eq!(decoder.feed(&[0xF0, 0x7F, 0x7F, 0x01, 0x02, 0, 0, 0, 0, 0xF7]), None);
+}
+
+/// LTC bits 64..80, 0011111111111101 in the order they're sent.
+const LTC_SYNC_WORD: u128 = 0xBFFC;
+
+/// Biphase mark audio for LTC frames from `start` seconds at `fps`, between
+/// a few bits of zeros for the decoder to find the edges.
+fn ltc_audio(rate: u32, fps: u32, start: u32, frames: u32) -> Vec<f32> {
+    let half = rate as f32 / (fps as f32 * 160.0);
+    let mut samples = vec![];
+    let mut level = 0.5;
+    let mut hold = |level: f32| {
+        let end = (samples.len() as f32 + half).round() as usize;
+        samples.resize(end, level);
+    };
+    let mut bit = |one: bool| {
+        level = -level;
+        hold(level);
+        if one {
+            level = -level;
+        }
+        hold(level);
+    };
+
+    for _ in 0..16 {
+        bit(false);
+    }
+    for n in 0..frames {
+        let frame = start * fps + n;
+        let (f, s) = (frame % fps, frame / fps);
+        let fields = [(0, f % 10), (8, f / 10), (16, s % 10), (24, s % 60 / 10), (32, s / 60 % 10), (40, s / 600)];
+        let mut bits = LTC_SYNC_WORD << 64;
+        for (at, value) in fields {
+            bits |= (value as u128) << at;
+        }
+        for i in 0..80 {
+            bit((bits >> i) & 1 == 1);
+        }
+    }
+    // Bits are read at the edge that starts the next one.
+    bit(false);
+    samples
+}
+
+/// LTC decodes from the sync word, timed at the right rate from the first frame.
+#[test]
+fn ltc_decode() {
+    use timecode::LtcDecoder;
+
+    for fps in [24, 25, 30] {
+        let mut decoder = LtcDecoder::new(48000);
+        let tcs = decoder.process(&ltc_audio(48000, fps, 10, 3));
+        assert_eq!(tcs.len(), 3, "{}fps", fps);
+        for (i, tc) in tcs.iter().enumerate() {
+            assert_eq!((tc.seconds, tc.frames, tc.fps), (10, i as u8 + 1, fps as f32), "{}fps", fps);
+        }
+    }
+}
+
+/// Chase runs on through a dropout, stops after `freewheel`, and relocks.
+#[test]
+fn timecode_chase() {
+    use timecode::{Chase, Timecode};
+
+    let tc = |seconds| Timecode { hours: 0, minutes: 0, seconds, frames: 0, fps: 25.0 };
+    let mut chase = Chase::new();
+    assert_eq!(chase.time(0.0), None);
+
+    chase.update(tc(10), 100.0);
+    assert_eq!(chase.time(100.5), Some(10.5));
+    assert_eq!(chase.time(104.0), Some(14.0));
+    assert_eq!(chase.time(105.5), None);
+
+    chase.update(tc(50), 106.0);
+    assert_eq!(chase.time(106.25), Some(50.25));
+}
+
+/// The OSC namespace sets show state, and the same addresses with no
+/// arguments answer with the current values.
+#[test]
+fn remote_osc() {
+    use remote::Remote;
+    use stagebridge::osc::{Message as OscMessage, Value as OscValue};
+
+    fn send(remote: &mut Remote, show: &mut Show, addr: &str, args: Vec<OscValue>) {
+        assert!(remote.handle(show, &OscMessage { addr: addr.into(), args }).is_empty(), "{}", addr);
+    }
+    fn query(remote: &mut Remote, show: &mut Show, addr: &str) -> Vec<OscValue> {
+        let answers = remote.handle(show, &OscMessage { addr: addr.into(), args: vec![] });
+        assert_eq!(answers.len(), 1, "{}", addr);
+        assert_eq!(answers[0].addr, addr);
+        answers[0].args.clone()
+    }
+    fn nums(args: Vec<OscValue>) -> Vec<f32> {
+        args.iter().map(|v| match *v {
+            OscValue::Float(f) => f,
+            OscValue::Int(i) => i as f32,
+            _ => panic!("{:?} is not a number", v),
+        }).collect()
+    }
+    let (f, name) = (OscValue::Float, |s: &str| OscValue::String(s.into()));
+
+    let mut remote = Remote::new();
+    let mut show = Show::new();
+    let r = &mut remote;
+
+    send(r, &mut show, "/scene", vec![name("drop-pulse")]);
+    assert_eq!(show.current, scene_pos("drop-pulse"));
+    send(r, &mut show, "/scene", vec![f(5.0), f(3.0)]);
+    assert_eq!(show.current, Some((5, 3)));
+    assert_eq!(nums(query(r, &mut show, "/scene")), [5.0, 3.0]);
+    for (x, y) in [(-1.0, 2.0), (2.0, -0.5), (8.0, 0.0)] {
+        send(r, &mut show, "/scene", vec![f(x), f(y)]);
+        assert_eq!(show.current, Some((5, 3)), "/scene {} {}", x, y);
+    }
+    send(r, &mut show, "/scene", vec![OscValue::Int(6), OscValue::Int(6)]);
+    assert_eq!(show.current, Some((6, 6)));
+
+    send(r, &mut show, "/master/alpha", vec![f(0.5)]);
+    send(r, &mut show, "/master/phi_mul", vec![f(2.0)]);
+    send(r, &mut show, "/master/blackout", vec![OscValue::Int(1)]);
+    assert_eq!((show.state.alpha, show.state.phi_mul, show.state.off), (0.5, 2.0, true));
+    assert_eq!(nums(query(r, &mut show, "/master/alpha")), [0.5]);
+    assert_eq!(nums(query(r, &mut show, "/master/blackout")), [1.0]);
+
+    send(r, &mut show, "/group/beams", vec![f(0.25)]);
+    assert_eq!(show.state.group_alpha[Group::Beams as usize], 0.25);
+    assert_eq!(nums(query(r, &mut show, "/group/beams")), [0.25]);
+
+    send(r, &mut show, "/color/0", vec![name("blue")]);
+    send(r, &mut show, "/color/1", vec![f(1.0), f(0.5), f(0.0)]);
+    assert_eq!(show.state.color0, ColorOp::Value(Color::BLUE));
+    assert_eq!(nums(query(r, &mut show, "/color/1")), [1.0, 0.5, 0.0, 0.0]);
+    send(r, &mut show, "/color/0/rainbow", vec![f(4.0)]);
+    assert_eq!(show.state.color0, ColorOp::Rainbow { pd: Pd(4, 1) });
+
+    send(r, &mut show, "/clock/source", vec![name("static")]);
+    send(r, &mut show, "/clock/bpm", vec![f(140.0)]);
+    assert!(matches!(show.time.source, ClockSource::Static { bpm } if bpm == 140.0));
+    assert_eq!(show.state.bpm, 140.0);
+    // No audio clock is running, so this is refused.
+    send(r, &mut show, "/clock/source", vec![name("audio")]);
+    let [OscValue::String(source)] = &query(r, &mut show, "/clock/source")[..] else { panic!() };
+    assert_eq!(source, "static");
+    assert_eq!(nums(query(r, &mut show, "/clock/bpm")), [140.0]);
+}
+
+/// Learn target names parse back to the same target, and off-grid targets
+/// have no name.
+#[test]
+fn mapping_target_names() {
+    use mapping::{SceneRef, Target};
+
+    let names = [
+        "alpha", "fr1", "swing", "group/beams", "param/3", "scene/low-0", "grid/2/7",
+        "go", "blackout", "clock_source", "phi_mul/2", "phi_mul/0.5", "stage/1/4",
+    ];
+    for name in names {
+        let target = Target::parse(name).unwrap_or_else(|| panic!("{}", name));
+        assert_eq!(target.name().as_deref(), Some(name));
+        assert_eq!(Target::parse(&target.name().unwrap()), Some(target));
+    }
+    for name in ["grid/8/0", "stage/2/0", "scene/nope", "group/lasers", "param/x", "nope", "grid/1"] {
+        assert_eq!(Target::parse(name), None, "{}", name);
+    }
+
+    let (x, y) = scene_pos("drop-pulse").unwrap();
+    assert_eq!(Target::Scene(SceneRef::Pos(x, y)).name().as_deref(), Some("scene/drop-pulse"));
+    assert_eq!(Target::Scene(SceneRef::Pos(8, 0)).name(), None);
+    assert_eq!(Target::Scene(SceneRef::Pos(0, 7)).name(), None);
+    assert_eq!(Target::Grid(0, 9).name(), None);
+}
+
+/// Notes, note offs and CCs parse with their channel, anything else doesn't.
+#[test]
+fn mapping_midi_msg() {
+    use mapping::MidiMsg;
+
+    let parse = |msg: &[u8]| MidiMsg::parse(msg).map(|m| (m.channel, m.note, m.cc, m.fr));
+    assert_eq!(parse(&[0x90, 60, 127]), Some((0, Some(60), None, 1.0)));
+    assert_eq!(parse(&[0x93, 60, 0]), Some((3, Some(60), None, 0.0)));
+    assert_eq!(parse(&[0x8F, 60, 64]), Some((15, Some(60), None, 0.0)));
+    assert_eq!(parse(&[0xB1, 7, 127]), Some((1, None, Some(7), 1.0)));
+    assert_eq!(parse(&[0xE0, 0, 64]), None);
+    assert_eq!(parse(&[0x90, 60]), None);
+    assert_eq!(parse(&[]), None);
+}
+
+/// Bindings of every kind are written to a mapping file and load back the same.
+#[test]
+fn mapping_save_load() {
+    use mapping::{Binding, MidiMap, MidiMsg, SceneRef, Target, Action, Fader};
+
+    let targets = [
+        Target::Scene(SceneRef::Name("low-0".into())),
+        Target::Scene(SceneRef::Pos(6, 6)),
+        Target::Grid(2, 7),
+        Target::Fader(Fader::Alpha),
+        Target::Fader(Fader::Group(Group::Beams)),
+        Target::Fader(Fader::Param(3)),
+        Target::Button(mapping::Button::Go),
+        Target::PhiMul(0.5),
+        Target::Stage(1, 4),
+    ];
+    let map = MidiMap {
+        port: "APC40".into(),
+        binds: targets.iter().enumerate().map(|(i, target)| Binding {
+            channel: (i % 2 == 0).then_some(i as u8),
+            note: (i % 3 != 0).then_some(40 + i as u8),
+            cc: (i % 3 == 0).then_some(i as u8),
+            target: target.clone(),
+        }).collect(),
+    };
+
+    let path = std::env::temp_dir().join(format!("msl-mapping-{}.toml", std::process::id()));
+    map.save(&path).unwrap();
+    let loaded = MidiMap::load(&path);
+    let _ = std::fs::remove_file(&path);
+    let loaded = loaded.unwrap();
+
+    assert_eq!(loaded.port, "APC40");
+    assert_eq!(loaded.binds.len(), targets.len());
+    for (a, b) in map.binds.iter().zip(&loaded.binds) {
+        assert_eq!((a.channel, a.note, a.cc, &a.target), (b.channel, b.note, b.cc, &b.target));
+    }
+
+    let press = MidiMsg::parse(&[0x92, 42, 1]).unwrap();
+    assert_eq!(loaded.map(&press), [Action::Grid(2, 7, 1.0 / 127.0)]);
+}
+
+/// Live output goes out every output tick and changes go out straight away;
+/// static output falls back to the keepalive.
+#[test]
+fn scheduler_output() {
+    let mut scheduler = Scheduler::new(200.0, 40.0);
+    scheduler.refresh = std::time::Duration::from_secs(1);
+    let mut sent = |at: f32, dmx: u8| scheduler.output_due(at, &[dmx]);
+
+    assert!(sent(0.0, 0));
+    assert!(!sent(0.005, 0));
+    assert!(sent(0.010, 1), "a change is not held back");
+    assert!(!sent(0.015, 1));
+    assert!(!sent(0.030, 1));
+    assert!(sent(0.035, 1), "live output resends every tick");
+    assert!(sent(0.061, 1));
+    assert!(sent(1.005, 1));
+    assert!(!sent(1.035, 1), "static output waits for the keepalive");
+    assert!(!sent(1.900, 1));
+    assert!(sent(2.010, 1));
+    assert!(sent(2.020, 2));
+    assert!(sent(2.050, 2));
+}
@@ -0,0 +1,282 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::audio::{self, AudioInput};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub fps: f32,
+}
+
+impl Timecode {
+    pub fn secs(&self) -> f32 {
+        self.hours as f32 * 3600.0
+            + self.minutes as f32 * 60.0
+            + self.seconds as f32
+            + self.frames as f32 / self.fps
+    }
+}
+
+fn mtc_fps(rate: u8) -> f32 {
+    match rate & 0b11 {
+        0 => 24.0,
+        1 => 25.0,
+        2 => 29.97,
+        _ => 30.0,
+    }
+}
+
+/// Assembles MIDI Time Code from quarter-frame and full-frame messages.
+#[derive(Default)]
+pub struct MtcDecoder {
+    pieces: [u8; 8],
+    received: u8,
+}
+
+impl MtcDecoder {
+    pub fn feed(&mut self, msg: &[u8]) -> Option<Timecode> {
+        match msg {
+            // Quarter frame: F1 0nnn dddd
+            [0xF1, data] => {
+                let piece = (data >> 4) & 0b111;
+                self.pieces[piece as usize] = data & 0x0F;
+                self.received |= 1 << piece;
+                if piece != 7 || self.received != 0xFF {
+                    return None;
+                }
+                self.received = 0;
+
+                let p = &self.pieces;
+                let fps = mtc_fps(p[7] >> 1);
+                let tc = Timecode {
+                    hours: p[6] | ((p[7] & 0b1) << 4),
+                    minutes: p[4] | (p[5] << 4),
+                    seconds: p[2] | (p[3] << 4),
+                    frames: p[0] | (p[1] << 4),
+                    fps,
+                };
+                // The full time was latched when piece 0 was sent, two frames ago.
+                Some(Timecode { frames: tc.frames + 2, ..tc })
+            },
+            // Full frame: F0 7F dev 01 01 hr mn sc fr F7
+            [0xF0, 0x7F, _, 0x01, 0x01, hr, mn, sc, fr, 0xF7] => {
+                self.received = 0;
+                Some(Timecode {
+                    hours: hr & 0x1F,
+                    minutes: *mn,
+                    seconds: *sc,
+                    frames: *fr,
+                    fps: mtc_fps(hr >> 5),
+                })
+            },
+            _ => None,
+        }
+    }
+}
+
+/// LTC sync word, bits 64..80 read least significant first.
+const LTC_SYNC: u128 = 0xBFFC;
+/// Frame rates told apart by the bit period. 29.97 is 30 with the drop frame flag.
+const LTC_RATES: [f32; 3] = [24.0, 25.0, 30.0];
+
+/// Decodes SMPTE linear timecode from audio samples.
+///
+/// LTC is biphase mark coded: every bit starts with a transition, and a 1 has
+/// a second transition halfway through.
+pub struct LtcDecoder {
+    rate: f32,
+    high: bool,
+    since_edge: f32,
+    period: f32,
+    half: bool,
+    bits: u128,
+}
+
+impl LtcDecoder {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: rate as f32,
+            high: false,
+            since_edge: 0.0,
+            // 80 bits per frame at 30fps until we've seen some bits.
+            period: rate as f32 / (80.0 * 30.0),
+            half: false,
+            bits: 0,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<Timecode> {
+        let mut frames = vec![];
+        for &sample in samples {
+            self.since_edge += 1.0;
+            let high = match self.high {
+                true => sample > -0.05,
+                false => sample > 0.05,
+            };
+            if high == self.high {
+                continue;
+            }
+            self.high = high;
+
+            let interval = std::mem::replace(&mut self.since_edge, 0.0);
+            if interval < 0.75 * self.period {
+                self.period += 0.05 * (2.0 * interval - self.period);
+                if self.half {
+                    self.half = false;
+                    frames.extend(self.bit(true));
+                } else {
+                    self.half = true;
+                }
+            } else {
+                self.period += 0.05 * (interval - self.period);
+                self.half = false;
+                frames.extend(self.bit(false));
+            }
+        }
+        frames
+    }
+
+    fn bit(&mut self, bit: bool) -> Option<Timecode> {
+        self.bits = (self.bits >> 1) | ((bit as u128) << 79);
+        if (self.bits >> 64) & 0xFFFF != LTC_SYNC {
+            return None;
+        }
+
+        let field = |start: u32, len: u32| ((self.bits >> start) & ((1 << len) - 1)) as u8;
+        let tc = Timecode {
+            hours: field(48, 4) + 10 * field(56, 2),
+            minutes: field(32, 4) + 10 * field(40, 3),
+            seconds: field(16, 4) + 10 * field(24, 3),
+            frames: field(0, 4) + 10 * field(8, 2),
+            fps: self.fps(field(10, 1) == 1),
+        };
+        // The frame we just finished reading is already over.
+        Some(Timecode { frames: tc.frames + 1, ..tc })
+    }
+
+    /// The standard frame rate nearest the measured bit period, 80 bits a frame.
+    fn fps(&self, drop_frame: bool) -> f32 {
+        let measured = self.rate / (80.0 * self.period);
+        let fps = LTC_RATES
+            .into_iter()
+            .min_by(|a, b| (a - measured).abs().total_cmp(&(b - measured).abs()))
+            .unwrap();
+        if drop_frame && fps == 30.0 { 29.97 } else { fps }
+    }
+}
+
+/// Follows incoming timecode, freewheeling through dropouts.
+///
+/// Times passed in are seconds from the main loop's `Clock`.
+pub struct Chase {
+    anchor: Option<(f32, f32)>,
+    last: Option<f32>,
+    /// How long to keep running without timecode before stopping.
+    pub freewheel: Duration,
+}
+
+impl Chase {
+    pub fn new() -> Self {
+        Self {
+            anchor: None,
+            last: None,
+            freewheel: Duration::from_secs(5),
+        }
+    }
+
+    pub fn update(&mut self, tc: Timecode, now: f32) {
+        let secs = tc.secs();
+        match self.time(now) {
+            None => log::info!("Timecode locked at {:?}", tc),
+            Some(t) if (t - secs).abs() > 0.5 => log::info!("Timecode jumped from {:.2}s to {:?}", t, tc),
+            _ => {},
+        }
+        self.anchor = Some((secs, now));
+        self.last = Some(now);
+    }
+
+    /// Current timecode in seconds, or `None` if it was never locked or has
+    /// been lost for longer than `freewheel`.
+    pub fn time(&self, now: f32) -> Option<f32> {
+        let (secs, at) = self.anchor?;
+        if now - self.last? > self.freewheel.as_secs_f32() {
+            return None;
+        }
+        Some(secs + (now - at).max(0.0))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum TimecodeInput {
+    /// MTC from the first MIDI input port whose name contains this string.
+    Mtc(String),
+    /// LTC from an audio input.
+    Ltc(AudioInput),
+}
+
+impl TimecodeInput {
+    /// `mtc:<port>` or `ltc:<audio input>`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.split_once(':')? {
+            ("mtc", port) => Some(TimecodeInput::Mtc(port.into())),
+            ("ltc", input) => Some(TimecodeInput::Ltc(AudioInput::parse(input))),
+            _ => None,
+        }
+    }
+}
+
+/// Start decoding `input` on a background thread.
+pub fn spawn(input: TimecodeInput) -> mpsc::Receiver<Timecode> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = match &input {
+            TimecodeInput::Mtc(port) => run_mtc(port, tx),
+            TimecodeInput::Ltc(audio) => {
+                let mut decoder = None;
+                audio::stream(audio, |rate, samples| {
+                    let decoder = decoder.get_or_insert_with(|| LtcDecoder::new(rate));
+                    for tc in decoder.process(samples) {
+                        tx.send(tc)?;
+                    }
+                    Ok(())
+                })
+            },
+        };
+        if let Err(e) = res {
+            log::warn!("Timecode {:?} stopped: {:?}", input, e);
+        }
+    });
+    rx
+}
+
+fn run_mtc(port: &str, tx: mpsc::Sender<Timecode>) -> anyhow::Result<()> {
+    let mut midi = midir::MidiInput::new("milstrikelive-mtc")?;
+    midi.ignore(midir::Ignore::None);
+
+    let ports = midi.ports();
+    let found = ports
+        .iter()
+        .find(|p| midi.port_name(p).map(|name| name.contains(port)).unwrap_or(false))
+        .ok_or_else(|| anyhow!("no MIDI input matching {:?}", port))?;
+    log::info!("Chasing MTC from {}", midi.port_name(found)?);
+
+    let mut decoder = MtcDecoder::default();
+    let _conn = midi
+        .connect(found, "mtc", move |_, msg, _| {
+            if let Some(tc) = decoder.feed(msg) {
+                let _ = tx.send(tc);
+            }
+        }, ())
+        .map_err(|e| anyhow!("connecting to {:?}: {}", port, e))?;
+
+    loop {
+        thread::park();
+    }
+}
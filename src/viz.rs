@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use stagebridge::num::Float;
+use stagebridge::osc::{Message as OscMessage, Osc, Value as OscValue};
+
+use crate::*;
+
+/// A visualizer listening for OSC.
+#[derive(Clone, Debug)]
+pub struct VizTarget {
+    pub addr: String,
+    /// Address template, with `{}` replaced by the message address, e.g. `/milstrike{}`.
+    pub template: String,
+}
+
+impl VizTarget {
+    /// `host:port` or `host:port=template`.
+    pub fn parse(s: &str) -> Self {
+        match s.split_once('=') {
+            Some((addr, template)) => Self { addr: addr.into(), template: template.into() },
+            None => Self { addr: s.into(), template: "{}".into() },
+        }
+    }
+
+    fn addr(&self, addr: &str) -> String {
+        match self.template.contains("{}") {
+            true => self.template.replace("{}", addr),
+            false => format!("{}{}", self.template, addr),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Floats(Vec<f32>),
+    Str(String),
+}
+
+impl Value {
+    fn args(&self) -> Vec<OscValue> {
+        match self {
+            Value::Floats(fs) => fs.iter().map(|&f| OscValue::Float(f)).collect(),
+            Value::Str(s) => vec![OscValue::String(s.clone())],
+        }
+    }
+}
+
+struct Slot {
+    value: Value,
+    sent: Option<(Value, Instant)>,
+}
+
+/// Visualizer output. Continuous values are only sent when they change, at
+/// most once per `interval` each, and resent every `keepalive` so a
+/// restarted visualizer catches up. Events like beats are sent as they happen.
+pub struct Viz {
+    pub targets: Vec<VizTarget>,
+    pub interval: Duration,
+    pub keepalive: Duration,
+
+    slots: BTreeMap<String, Slot>,
+    switches: u64,
+    events: Vec<(String, Vec<OscValue>)>,
+    beat_last: bool,
+}
+
+impl Viz {
+    pub fn new(targets: Vec<VizTarget>) -> Self {
+        Self {
+            targets,
+            interval: Duration::from_millis(20),
+            keepalive: Duration::from_secs(1),
+
+            slots: BTreeMap::new(),
+            events: vec![],
+            switches: 0,
+            beat_last: false,
+        }
+    }
+
+    /// Targets from `MSL_VIZ` as a comma separated list of `host:port[=template]`,
+    /// defaulting to 127.0.0.1:7778. `MSL_VIZ_RATE` sets the max rate in Hz
+    /// and `MSL_VIZ_KEEPALIVE` the keepalive in seconds.
+    pub fn from_env() -> Self {
+        let targets = std::env::var("MSL_VIZ").unwrap_or_else(|_| "127.0.0.1:7778".into());
+        let mut viz = Self::new(targets.split(',').filter(|s| !s.is_empty()).map(VizTarget::parse).collect());
+        // A nonzero duration of `secs(value)` seconds, or the default with a warning.
+        let duration = |name: &str, secs: fn(f32) -> f32| {
+            let s = std::env::var(name).ok()?;
+            let duration = s.parse::<f32>()
+                .ok()
+                .and_then(|value| Duration::try_from_secs_f32(secs(value)).ok())
+                .filter(|d| !d.is_zero());
+            if duration.is_none() {
+                log::warn!("Invalid {} {:?}, using the default", name, s);
+            }
+            duration
+        };
+        if let Some(interval) = duration("MSL_VIZ_RATE", |rate| 1.0 / rate) {
+            viz.interval = interval;
+        }
+        if let Some(keepalive) = duration("MSL_VIZ_KEEPALIVE", |secs| secs) {
+            viz.keepalive = keepalive;
+        }
+        viz
+    }
+
+    fn set(&mut self, addr: &str, value: Value) {
+        match self.slots.get_mut(addr) {
+            Some(slot) => slot.value = value,
+            None => {
+                self.slots.insert(addr.into(), Slot { value, sent: None });
+            },
+        }
+    }
+
+    pub fn beat(&mut self) {
+        self.events.push(("/beat".into(), vec![]));
+    }
+
+    pub fn switch(&mut self, name: &str) {
+        self.events.push((format!("/stage/{}", name), vec![]));
+    }
+
+    /// Take the current values from the show.
+    pub fn update(&mut self, show: &Show) {
+        let state = &show.state;
+        // White is carried on the w channel, which the visualizer doesn't have.
+        let rgb = |c: Color| Value::Floats(vec![
+            (c.r + c.w).min(1.0),
+            (c.g + c.w).min(1.0),
+            (c.b + c.w).min(1.0),
+        ]);
+
+        self.set("/alpha", Value::Floats(vec![state.alpha * state.viz_alpha]));
+        self.set("/color", rgb(state.color0()));
+        self.set("/color1", rgb(state.color1()));
+        self.set("/pd", Value::Floats(vec![60.0 / state.bpm * state.viz_pd.fr() * state.phi_mul]));
+        self.set("/phrase", Value::Floats(vec![state.phi]));
+        if let Some((x, y)) = show.current {
+            self.set("/scene", Value::Str(SCENES[y as usize][x as usize].into()));
+        }
+
+        let stages = &show.stages;
+        if stages.switches != self.switches {
+            self.switches = stages.switches;
+            if let Some(name) = &stages.active {
+                self.switch(name);
+            }
+        }
+        for (addr, value) in &stages.params {
+            self.set(addr, Value::Floats(vec![*value]));
+        }
+
+        if state.viz_beat {
+            let beat = state.phi(state.viz_pd).bsquare(1.0, 0.5);
+            if beat && !self.beat_last {
+                self.beat();
+            }
+            self.beat_last = beat;
+        }
+    }
+
+    /// Send pending events and any values that are due.
+    pub async fn flush(&mut self, osc: &Osc) {
+        let now = Instant::now();
+
+        let mut msgs = std::mem::take(&mut self.events);
+        for (addr, slot) in &mut self.slots {
+            let due = match &slot.sent {
+                None => true,
+                Some((sent, at)) => {
+                    let since = now.duration_since(*at);
+                    (since >= self.interval && *sent != slot.value) || since >= self.keepalive
+                },
+            };
+            if due {
+                msgs.push((addr.clone(), slot.value.args()));
+                slot.sent = Some((slot.value.clone(), now));
+            }
+        }
+
+        for (addr, args) in msgs {
+            for target in &self.targets {
+                osc.send(&target.addr, OscMessage {
+                    addr: target.addr(&addr),
+                    args: args.clone(),
+                })
+                .await;
+            }
+        }
+    }
+}
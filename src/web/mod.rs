@@ -0,0 +1,224 @@
+//! Browser control surface.
+//!
+//! `GET /` serves the control page and `GET /ws` upgrades to a WebSocket.
+//! The page sends commands as `{"addr": "/scene", "args": [0, 3]}`, using the
+//! same namespace as the OSC API in `remote`, and receives a `Status` snapshot
+//! as JSON whenever the show renders a new one.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State as Shared;
+use axum::response::{Html, IntoResponse, Json};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+
+use stagebridge::osc::{Message as OscMessage, Value as OscValue};
+
+use crate::*;
+
+const INDEX: &str = include_str!("index.html");
+
+/// How often status is published to connected pages.
+const STATUS_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Serialize)]
+struct Fixture {
+    name: String,
+    /// Display color, 0..1.
+    rgb: [f32; 3],
+    /// Top-down position on the preview, 0..1.
+    x: f32,
+    y: f32,
+}
+
+impl Fixture {
+    fn new(name: String, color: Color, pos: StagePos) -> Self {
+        let ch = |c: f32| ((c + color.w) * color.a).clamp(0.0, 1.0);
+        Self {
+            name,
+            rgb: [ch(color.r), ch(color.g), ch(color.b)],
+            x: pos.x,
+            y: 0.1 + 0.7 * pos.z + 0.15 * (1.0 - pos.y),
+        }
+    }
+}
+
+/// Everything the page shows, sent as one message per frame.
+#[derive(Serialize)]
+pub struct Status {
+    bpm: f32,
+    phi: f32,
+    source: &'static str,
+    alpha: f32,
+    fr0: f32,
+    fr1: f32,
+    phi_mul: f32,
+    blackout: bool,
+    groups: Vec<(&'static str, f32)>,
+    color0: [f32; 4],
+    color1: [f32; 4],
+    scene: Option<(u8, u8)>,
+    /// Armed MIDI learn target.
+    learn: Option<String>,
+    fixtures: Vec<Fixture>,
+}
+
+impl Status {
+    pub fn new(show: &Show, lights: &Lights) -> Self {
+        let state = &show.state;
+        let rgbw = |c: Color| [c.r, c.g, c.b, c.w];
+
+        let mut fixtures = vec![];
+        for (i, (par, f)) in lights.pars.iter().zip(&PATCH.pars).enumerate() {
+            fixtures.push(Fixture::new(format!("par{}", i), par.color, f.pos));
+        }
+        for (i, (beam, f)) in lights.beams.iter().zip(&PATCH.beams).enumerate() {
+            fixtures.push(Fixture::new(format!("beam{}", i), beam.color, f.pos));
+        }
+        fixtures.push(Fixture::new("strobe".into(), lights.strobe.color, PATCH.strobe.pos));
+        for (i, (bar, f)) in lights.bars.iter().zip(&PATCH.bars).enumerate() {
+            fixtures.push(Fixture::new(format!("bar{}", i), bar.color, f.pos));
+        }
+        for (i, (spider, f)) in lights.spiders.iter().zip(&PATCH.spiders).enumerate() {
+            fixtures.push(Fixture::new(format!("spider{}", i), spider.color0, f.pos));
+        }
+
+        Self {
+            bpm: state.bpm,
+            phi: state.phi,
+            source: match show.time.source {
+                ClockSource::Osc => "osc",
+                ClockSource::Static { .. } => "static",
+                ClockSource::Audio => "audio",
+                ClockSource::Timecode { .. } => "timecode",
+            },
+            alpha: state.alpha,
+            fr0: state.fr0,
+            fr1: state.fr1,
+            phi_mul: state.phi_mul,
+            blackout: state.off,
+            groups: Group::ALL.into_iter().map(|g| (g.name(), state.group_alpha[g as usize])).collect(),
+            color0: rgbw(state.color0.apply(state)),
+            color1: rgbw(state.color1.apply(state)),
+            scene: show.current,
+            learn: show.learn.as_ref().and_then(|t| t.name()),
+            fixtures,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Command {
+    addr: String,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+}
+
+impl From<Command> for OscMessage {
+    fn from(cmd: Command) -> Self {
+        use serde_json::Value;
+        let args = cmd.args.into_iter().filter_map(|v| match v {
+            Value::Number(n) if n.is_i64() => Some(OscValue::Int(n.as_i64()? as i32)),
+            Value::Number(n) => Some(OscValue::Float(n.as_f64()? as f32)),
+            Value::Bool(b) => Some(OscValue::Int(b as i32)),
+            Value::String(s) => Some(OscValue::String(s)),
+            _ => None,
+        });
+        OscMessage { addr: cmd.addr, args: args.collect() }
+    }
+}
+
+#[derive(Clone)]
+struct Page {
+    commands: mpsc::UnboundedSender<OscMessage>,
+    status: watch::Receiver<String>,
+}
+
+/// The render loop's end of the web server.
+pub struct Web {
+    /// Where the server is listening, with the port filled in if 0 was asked for.
+    pub addr: SocketAddr,
+    commands: mpsc::UnboundedReceiver<OscMessage>,
+    status: watch::Sender<String>,
+    last: Option<Instant>,
+}
+
+impl Web {
+    /// Start serving on `addr` in the background.
+    pub fn spawn(addr: SocketAddr) -> Self {
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let (status, status_rx) = watch::channel(String::new());
+
+        let app = Router::new()
+            .route("/", get(|| async { Html(INDEX) }))
+            .route("/scenes", get(|| async { Json(SCENES) }))
+            .route("/ws", get(ws))
+            .with_state(Page { commands: commands_tx, status: status_rx });
+
+        let server = axum::Server::bind(&addr).serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(async move {
+            log::info!("Serving web control on http://{}", addr);
+            if let Err(e) = server.await {
+                log::warn!("Web server stopped: {:?}", e);
+            }
+        });
+
+        Self { addr, commands, status, last: None }
+    }
+
+    /// Commands received since the last call.
+    pub fn commands(&mut self) -> impl Iterator<Item = OscMessage> + '_ {
+        std::iter::from_fn(|| self.commands.try_recv().ok())
+    }
+
+    /// Publish the current frame to connected pages, at most every `STATUS_INTERVAL`.
+    pub fn publish(&mut self, show: &Show, lights: &Lights) {
+        if self.last.is_some_and(|last| last.elapsed() < STATUS_INTERVAL) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        match serde_json::to_string(&Status::new(show, lights)) {
+            Ok(json) => {
+                self.status.send_replace(json);
+            },
+            Err(e) => log::warn!("Failed to serialize status: {:?}", e),
+        }
+    }
+}
+
+async fn ws(upgrade: WebSocketUpgrade, Shared(page): Shared<Page>) -> impl IntoResponse {
+    upgrade.on_upgrade(move |socket| session(socket, page))
+}
+
+async fn session(mut socket: WebSocket, mut page: Page) {
+    log::info!("Web control connected");
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<Command>(&text) {
+                    Ok(cmd) => {
+                        let _ = page.commands.send(cmd.into());
+                    },
+                    Err(e) => log::warn!("Bad web command {:?}: {}", text, e),
+                },
+                Some(Ok(_)) => {},
+                _ => break,
+            },
+            changed = page.status.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let status = page.status.borrow_and_update().clone();
+                if socket.send(Message::Text(status)).await.is_err() {
+                    break;
+                }
+            },
+        }
+    }
+    log::info!("Web control disconnected");
+}